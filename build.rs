@@ -0,0 +1,4 @@
+fn main() {
+    // Declares the cfg that enables `proc-macro2`'s span locations, for the `unexpected_cfgs` lint.
+    println!("cargo:rustc-check-cfg=cfg(procmacro2_semver_exempt)");
+}
@@ -19,4 +19,110 @@ macro_rules! parser {
             $block
         }
     };
-}
\ No newline at end of file
+}
+#[macro_export]
+/// A macro to try any number of token parsers in order, returning the first that succeeds.
+///
+/// Every alternative is wrapped in `attempt`, so a multi-token branch that fails part way
+/// doesn't prevent the later branches from being tried, and the expected-error labels of
+/// all failed branches are merged. The input must support enough lookahead for the longest
+/// branch (see `Input::with_lookahead`).
+///
+/// ```rust
+/// # extern crate proc_macro;
+/// use combine::Parser;
+/// use combine_proc_macro::{choice_token, Input};
+/// use combine_proc_macro::parser::{keyword, puncts};
+///
+/// let mut op = choice_token![
+///     puncts("..=").map(|_| "inclusive"),
+///     puncts("..").map(|_| "exclusive"),
+///     keyword("to").map(|_| "inclusive"),
+/// ];
+/// let input = Input::from("..= 5".parse::<proc_macro2::TokenStream>().unwrap());
+/// assert_eq!(op.easy_parse(input.with_lookahead(3)).map(|(kind, _)| kind), Ok("inclusive"));
+/// ```
+macro_rules! choice_token {
+    ($first:expr $(,)?) => {
+        ::combine::attempt($first)
+    };
+    ($first:expr, $($rest:expr),+ $(,)?) => {
+        ::combine::Parser::or(::combine::attempt($first), $crate::choice_token!($($rest),+))
+    };
+}
+#[macro_export]
+/// A macro to declare keyword parsers for a DSL, similar to `syn::custom_keyword!`.
+///
+/// Each name becomes a function returning a parser for that keyword (see
+/// `parser::keyword_ident`), whose expected-error label is the keyword itself. A trailing
+/// underscore is stripped from the keyword so that Rust keywords can be declared (e.g.
+/// `where_` parses `where`). Prefix the list with `ignore_case:` to match regardless of
+/// ASCII case.
+///
+/// ```rust
+/// # extern crate proc_macro;
+/// use combine::Parser;
+/// use combine_proc_macro::Input;
+///
+/// mod kw {
+///     combine_proc_macro::kw!(select, from, where_);
+/// }
+/// mod sql {
+///     combine_proc_macro::kw!(ignore_case: select);
+/// }
+///
+/// let input = || Input::from("SELECT x".parse::<proc_macro2::TokenStream>().unwrap());
+/// assert!(kw::select().parse(input().with_lookahead(1)).is_err());
+/// let (select, _) = sql::select().parse(input().with_lookahead(1)).unwrap();
+/// assert_eq!(select.to_string(), "SELECT");
+///
+/// let input = Input::from("where".parse::<proc_macro2::TokenStream>().unwrap());
+/// assert!(kw::where_().parse(input.with_lookahead(1)).is_ok());
+/// ```
+macro_rules! kw {
+    (@define $name:ident, $parser:ident) => {
+        pub fn $name<I>() -> $crate::parser::KeywordIdent<I>
+        where
+            I: ::combine::Stream<Item = $crate::Token>,
+            I::Error: ::combine::ParseError<I::Item, I::Range, I::Position>,
+        {
+            $crate::parser::$parser(stringify!($name).trim_end_matches('_'))
+        }
+    };
+    (ignore_case: $($name:ident),+ $(,)?) => {
+        $($crate::kw!(@define $name, keyword_ident_ignore_case);)+
+    };
+    ($($name:ident),+ $(,)?) => {
+        $($crate::kw!(@define $name, keyword_ident);)+
+    };
+}
+#[macro_export]
+/// A macro to declare an operator parser for a DSL, similar to `syn::custom_punctuation!`.
+///
+/// The operator may span several joint punctuation tokens (e.g. `"->"` or `"<=>"`); the
+/// declared function returns a parser for it (see `parser::op_seq`) producing the span of the
+/// whole operator.
+///
+/// ```rust
+/// # extern crate proc_macro;
+/// use combine::Parser;
+/// use combine_proc_macro::Input;
+///
+/// mod punct {
+///     combine_proc_macro::custom_punct!(spaceship, "<=>");
+/// }
+///
+/// let input = Input::from("<=> x".parse::<proc_macro2::TokenStream>().unwrap());
+/// assert!(punct::spaceship().parse(input.with_lookahead(3)).is_ok());
+/// ```
+macro_rules! custom_punct {
+    ($name:ident, $op:expr) => {
+        pub fn $name<I>() -> impl ::combine::Parser<Input = I, Output = $crate::Span>
+        where
+            I: ::combine::Stream<Item = $crate::Token>,
+            I::Error: ::combine::ParseError<I::Item, I::Range, I::Position>,
+        {
+            $crate::parser::op_seq($op)
+        }
+    };
+}
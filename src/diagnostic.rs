@@ -1,10 +1,8 @@
 //! Utilities to generate diagnostic error messages.
 
-use crate::input::Token;
+use crate::input::{write_tokens, Token};
 use combine::ParseError;
 use combine::stream::StreamOnce;
-use proc_macro2::{TokenTree, TokenStream};
-use std::convert::TryFrom;
 use std::fmt;
 
 const DEFAULT_MAX_TRAILING: usize = 50;
@@ -24,7 +22,10 @@ const DEFAULT_MAX_TRAILING: usize = 50;
 /// ```
 #[derive(Debug)]
 pub struct Incomplete {
-    trailing: Vec<TokenTree>,
+    trailing: Vec<Token>,
+
+    // The number of trailing tokens, including those past `max_trailing` which aren't kept
+    total: usize,
 
     // The maximum number of
     max_trailing: usize,
@@ -37,15 +38,17 @@ impl Incomplete {
         I::Error: ParseError<I::Item, I::Range, I::Position>,
     {
         let mut trailing = Vec::new();
+        let mut total = 0;
         while let Ok(tok) = input.uncons() {
-            trailing.extend(TokenTree::try_from(tok).into_iter());
-            if trailing.len() > DEFAULT_MAX_TRAILING {
-                break;
+            if trailing.len() < DEFAULT_MAX_TRAILING {
+                trailing.push(tok);
             }
+            total += 1;
         }
-        if trailing.len() > 0 {
+        if total > 0 {
             Some(Incomplete {
                 trailing,
+                total,
                 max_trailing: DEFAULT_MAX_TRAILING,
             })
         } else {
@@ -56,13 +59,61 @@ impl Incomplete {
 
 impl fmt::Display for Incomplete {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let total = self.trailing.len();
-        let mut stream = TokenStream::new();
-        stream.extend(self.trailing.iter().take(self.max_trailing).cloned());
-        if total > self.max_trailing {
-            write!(f, "{} [and {} more ...]", stream, total - self.max_trailing)
-        } else {
-            write!(f, "{}", stream)
+        // Write the tokens themselves rather than a `TokenStream`, which can't represent
+        // the unmatched delimiters of a partially parsed input
+        write_tokens(f, self.trailing.iter().take(self.max_trailing))?;
+        if self.total > self.max_trailing {
+            write!(f, " [and {} more tokens]", self.total - self.max_trailing)?;
         }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Incomplete;
+    use crate::{Input, Token};
+    use combine::stream::{IteratorStream, StreamOnce};
+    use proc_macro2::Span;
+
+    fn trailing(source: &str, skip: usize) -> Option<String> {
+        let mut input = Input::parse_str(source).unwrap();
+        for _ in 0..skip {
+            input.uncons().unwrap();
+        }
+        Incomplete::from_stream(input).map(|diagnostic| diagnostic.to_string())
+    }
+
+    #[test]
+    fn complete() {
+        assert_eq!(trailing("{ a }", 3), None);
+    }
+
+    #[test]
+    fn trailing_tokens() {
+        assert_eq!(trailing("a b -> ( c )", 1), Some("b -> ( c )".to_string()));
+    }
+
+    #[test]
+    fn unmatched_close() {
+        assert_eq!(trailing("{ a } b", 2), Some("} b".to_string()));
+        assert_eq!(trailing("{ a }", 2), Some("}".to_string()));
+    }
+
+    #[test]
+    fn mismatched_delimiters() {
+        let span = Span::call_site();
+        let tokens = vec![Token::delim('(', span), Token::ident("a", span), Token::delim(']', span)];
+        let diagnostic = Incomplete::from_stream(IteratorStream::new(tokens)).unwrap();
+        assert_eq!(diagnostic.to_string(), "( a ]");
+    }
+
+    #[test]
+    fn truncated() {
+        let source = vec!["x"; 100].join(" ");
+        let diagnostic = trailing(&source, 0).unwrap();
+        assert_eq!(diagnostic, format!("{} [and 50 more tokens]", vec!["x"; 50].join(" ")));
+        let diagnostic = trailing(&vec!["x"; 51].join(" "), 0).unwrap();
+        assert!(diagnostic.ends_with("x [and 1 more tokens]"), "{}", diagnostic);
     }
 }
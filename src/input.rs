@@ -1,124 +1,532 @@
 //! Wrappers and transforms to around `proc_macro` types to implement `combine` traits.
 
-use combine::{Positioned, StreamOnce};
+use crate::parser::eof;
+use combine::{Parser, Positioned, Stream, StreamOnce};
+use combine::stream::{FullRangeStream, RangeStreamOnce, Resetable};
 use combine::stream::StreamErrorFor;
-use combine::stream::easy::Error;
+use combine::stream::easy::{self, Error};
 use combine::stream::buffered::BufferedStream;
 use combine::stream::state::{DefaultPositioned, Positioner, State};
 use proc_macro::{TokenStream as TokenStreamBuiltin};
-use proc_macro2::{Delimiter, Ident, Punct, Literal, Span, TokenStream, TokenTree};
+use proc_macro2::{Delimiter, Group, Ident, LexError, Punct, Literal, Spacing, Span, TokenStream, TokenTree};
 use proc_macro2::token_stream::IntoIter;
 use std::cmp::Ordering;
+use std::borrow::Cow;
 use std::convert::TryFrom;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::FromIterator;
+use std::ops::{Deref, Range};
+use std::rc::Rc;
+use std::str::FromStr;
 
+/// A stream of the tokens in a `TokenStream`, where groups are flattened into delimiter tokens.
+///
+/// The tokens are flattened up front into a buffer shared between clones of the input, so a
+/// checkpoint is just an index into the buffer and the input can backtrack any distance.
+#[derive(Clone)]
 pub struct Input {
-    source_stack: Vec<(IntoIter, Option<Token>)>,
-    source_pos: usize,
+    source: Source,
+    tokens: Rc<Vec<Token>>,
+    groups: Rc<Vec<Option<(usize, usize)>>>,
+    depths: Rc<Vec<usize>>,
+    positions: Rc<Vec<SpanPosition>>,
+    none_extents: Rc<Vec<(Range<usize>, Span)>>,
+    index: usize,
+    consumed: usize,
+    pushback: Vec<Token>,
+    pushback_span: Option<Span>,
+    end_span: Option<Span>,
+    options: InputOptions,
+    depth_exceeded: bool,
+}
+
+/// The options an `Input` flattens its tokens with, which carry over to inputs created by
+/// `enter_group`.
+#[derive(Copy, Clone, Debug, Default)]
+struct InputOptions {
+    max_depth: Option<usize>,
+    none_groups: bool,
+    shallow: bool,
+}
+
+/// The tokens an `Input` is buffered from.
+#[derive(Clone)]
+enum Source {
+    Stream(TokenStream),
+    /// Tokens collected into an input (see `FromIterator<Token>`), which are buffered as they are.
+    Tokens(Rc<Vec<Token>>),
 }
 
 impl Input {
+    /// Lexes `source` into an input (e.g. `Input::parse_str("{ hello \"x\" ! }")`).
+    ///
+    /// Useful for tests and examples, where there is no `TokenStream` from the compiler.
+    pub fn parse_str(source: &str) -> Result<Input, LexError> {
+        source.parse()
+    }
+
+    /// Returns the number of tokens remaining in the input.
+    ///
+    /// Groups count as their contents plus both delimiter tokens, including the close delimiters
+    /// of groups which the input is currently inside of. See `SpanPosition::index` for the number
+    /// of tokens consumed so far.
+    pub fn len(&self) -> usize {
+        self.tokens.len() - self.index + self.pushback.len()
+    }
+
     /// Returns `true` if the input contains no more tokens.
     pub fn is_empty(&self) -> bool {
-        self.source_stack.is_empty()
+        self.len() == 0
+    }
+
+    /// Returns the next token without consuming it, or `None` at the end of input.
+    pub fn peek(&self) -> Option<&Token> {
+        self.pushback.last().or_else(|| self.tokens.get(self.index))
+    }
+
+    /// Returns an iterator over the remaining tokens, without consuming them.
+    pub fn tokens(&self) -> impl Iterator<Item = &Token> {
+        self.pushback.iter().rev().chain(&self.tokens[self.index..])
+    }
+
+    /// Pushes `tok` back onto the front of the input, so it's the next token to be consumed.
+    ///
+    /// This lets hand-written parsers un-read a token, or split one into several (pushing
+    /// the rest back after consuming the first part). Pushed back tokens are restored by
+    /// `reset` like any other, and each has its own position once consumed (so the position
+    /// index counts them, see `SpanPosition::index`).
+    pub fn push_front(&mut self, tok: Token) {
+        self.pushback.push(tok);
+    }
+
+    /// Reconstructs the remaining tokens into a `TokenStream`, without consuming them.
+    ///
+    /// Delimiters are paired back into groups. The close delimiters of groups which were
+    /// entered before the current position are skipped, so e.g. the rest of `{ a ( b ) c }`
+    /// after `{ a` is `( b ) c`, and groups which aren't closed are closed at the end.
+    ///
+    /// Returns an error if a close delimiter doesn't match its open delimiter, which can
+    /// only happen for tokens collected into an input or pushed back with `push_front`.
+    pub fn to_token_stream(&self) -> Result<TokenStream, UnbalancedDelimError> {
+        tokens_to_stream(balance_delims(self.tokens(), false))
+    }
+
+    /// Returns the next `k` tokens without consuming them (fewer near the end of input).
+    ///
+    /// The tokens are borrowed from the input's buffer, unless tokens were pushed back with
+    /// `push_front`.
+    pub fn peek_n(&self, k: usize) -> Cow<'_, [Token]> {
+        if self.pushback.is_empty() {
+            let end = self.tokens.len().min(self.index + k);
+            Cow::Borrowed(&self.tokens[self.index..end])
+        } else {
+            Cow::Owned(self.tokens().take(k).cloned().collect())
+        }
     }
 
     /// Wraps the input in a BufferedStream that supports lookahead grammars.
     ///
     /// By default `combine` produces an LL(1) parser, unless the `attempt`
     /// combinator is used, so `1` is the recommended default value for `k`.
+    ///
+    /// The stream keeps the last `k` tokens buffered, so an LL(k) grammar may
+    /// backtrack (e.g. with `attempt` or `look_ahead`) over at most `k` tokens;
+    /// backtracking further fails with a "Backtracked to far" error. Parsers
+    /// which look ahead internally document the `k` they need (e.g. `parser::puncts`
+    /// needs the length of the operator).
+    ///
+    /// `Input` is itself a `Stream` which can backtrack any distance, so wrapping
+    /// it is optional.
+    ///
+    /// ```rust
+    /// # extern crate proc_macro;
+    /// use combine::{attempt, Parser};
+    /// use combine_proc_macro::Input;
+    /// use combine_proc_macro::parser::{ident, punct};
+    ///
+    /// // LL(2): both branches start with an ident.
+    /// let mut call = attempt((ident(), punct('!')).map(|_| "macro")).or(ident().map(|_| "ident"));
+    /// let input = Input::from("foo bar".parse::<proc_macro2::TokenStream>().unwrap());
+    /// assert_eq!(call.parse(input.with_lookahead(2)).map(|(kind, _)| kind), Ok("ident"));
+    /// ```
     pub fn with_lookahead(self, k: usize) -> BufferedStream<State<Input, SpanPosition>> {
         BufferedStream::new(State::new(self), k)
     }
 
+    /// Limits how deeply groups may be nested in the input.
+    ///
+    /// Once a group nested more than `max_depth` levels deep is reached, every following
+    /// `uncons` fails with a "maximum nesting depth exceeded" error. This bounds the work
+    /// (and stack growth) of recursive grammars on adversarial or generated input.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Input {
+        self.options.max_depth = Some(max_depth);
+        self.buffer_tokens();
+        self
+    }
+
+    /// Yields `Delimiter::None` groups as single `Token::Group` tokens.
+    ///
+    /// Such invisible groups come from the expansion of other macros (e.g. a `$e:expr`
+    /// fragment), and by default their contents are flattened into the stream as if the
+    /// group wasn't there. Keeping the groups lets parsers capture them intact for
+    /// re-emission (e.g. with `parser::tt`), preserving precedence and hygiene.
+    pub fn with_none_groups(mut self) -> Input {
+        self.options.none_groups = true;
+        self.buffer_tokens();
+        self
+    }
+
+    /// Returns the `Delimiter::None` groups which were flattened into the input, in order of
+    /// their start.
+    ///
+    /// Each group is given as the range of token indices (see `SpanPosition::index`) its contents
+    /// were flattened into, along with the group's span. Tokens re-emitted from such a range can
+    /// be wrapped back into a `Delimiter::None` group with the span, to preserve the precedence
+    /// and hygiene of the macro expansion the group came from. Groups kept intact by
+    /// `with_none_groups` or `shallow` aren't included.
+    pub fn none_group_extents(&self) -> &[(Range<usize>, Span)] {
+        &self.none_extents
+    }
+
+    /// Yields every group as a single `Token::Group` instead of flattening it into delimiter tokens.
+    ///
+    /// Useful for grammars which treat blocks as opaque, since a group can't be desynchronized
+    /// by a stray close delimiter. Parsers matching delimiter tokens (e.g. `parser::delim` or
+    /// `parser::braced`) don't match in this mode; use `parser::group` instead.
+    pub fn shallow(mut self) -> Input {
+        self.options.shallow = true;
+        self.buffer_tokens();
+        self
+    }
+
+    /// Returns how many groups enclose the next token (`0` at the top level of the input).
+    pub fn depth(&self) -> usize {
+        self.depths[self.index]
+    }
+
+    /// Returns the delimiter of the innermost group enclosing the next token, if any.
+    ///
+    /// A close delimiter is part of the group it closes, so the group is still current until
+    /// its close delimiter is consumed.
+    pub fn current_delimiter(&self) -> Option<Delimiter> {
+        let (open, _) = self.groups[self.index]?;
+        match self.tokens[open] {
+            Token::Delim('(', _) => Some(Delimiter::Parenthesis),
+            Token::Delim('{', _) => Some(Delimiter::Brace),
+            Token::Delim('[', _) => Some(Delimiter::Bracket),
+            _ => None,
+        }
+    }
+
+    /// Returns the span of the open delimiter of the innermost group enclosing the next token.
+    pub fn open_span(&self) -> Option<Span> {
+        let (open, _) = self.groups[self.index]?;
+        Some(self.tokens[open].span())
+    }
+
+    /// Returns the span of the close delimiter of the innermost group enclosing the next token.
+    ///
+    /// Returns `None` at the top level, or if the group was cut off by `with_max_depth`.
+    pub fn close_span(&self) -> Option<Span> {
+        let (_, close) = self.groups[self.index]?;
+        self.tokens.get(close).map(Token::span)
+    }
+
+    /// Returns the span to report errors at the end of the input at.
+    ///
+    /// This is the span of the last token, or if the input has no tokens the span of the group's
+    /// close delimiter for an input created by `enter_group` (see `Token::Delim`) or else the call
+    /// site. The position of an `Input` at the end of input has this span, so "unexpected end of
+    /// input" errors point at the end of the macro's input rather than the whole invocation.
+    pub fn eof_span(&self) -> Span {
+        self.tokens.last().map(Token::span).or(self.end_span).unwrap_or_else(Span::call_site)
+    }
+
+    /// Returns a new input over only the tokens inside `group`, with the same options as this input.
+    ///
+    /// The new input has its own positions and end of input, so a nested body can be parsed with
+    /// a separate grammar and its trailing tokens checked separately (see `parser::nested`).
+    pub fn enter_group(&self, group: &Group) -> Input {
+        Input::new(Source::Stream(group.stream()), self.options, Some(span_close(group)))
+    }
+
+    fn new(source: Source, options: InputOptions, end_span: Option<Span>) -> Input {
+        let mut input = Input {
+            source,
+            tokens: Rc::new(Vec::new()),
+            groups: Rc::new(Vec::new()),
+            depths: Rc::new(Vec::new()),
+            positions: Rc::new(Vec::new()),
+            none_extents: Rc::new(Vec::new()),
+            index: 0,
+            consumed: 0,
+            pushback: Vec::new(),
+            pushback_span: None,
+            end_span,
+            options,
+            depth_exceeded: false,
+        };
+        input.buffer_tokens();
+        input
+    }
+
+    /// Flattens the source into the token buffer, according to the input's options.
+    ///
+    /// Options are set before parsing, so this also rewinds the input to the first token.
+    fn buffer_tokens(&mut self) {
+        let (tokens, none_extents, depth_exceeded) = match self.source {
+            Source::Stream(ref stream) => {
+                let mut flatten = Flatten {
+                    source_stack: vec![(stream.clone().into_iter(), None, None)],
+                    emitted: 0,
+                    none_extents: Vec::new(),
+                    options: self.options,
+                    depth_exceeded: false,
+                };
+                let tokens: Vec<Token> = flatten.by_ref().collect();
+                (tokens, flatten.none_extents, flatten.depth_exceeded)
+            }
+            Source::Tokens(ref tokens) => {
+                let mut depth = 0usize;
+                let end = tokens.iter().position(|tok| match tok {
+                    Token::Delim('(', _) | Token::Delim('{', _) | Token::Delim('[', _) => {
+                        depth += 1;
+                        matches!(self.options.max_depth, Some(max_depth) if depth > max_depth)
+                    }
+                    Token::Delim(_, _) => {
+                        depth = depth.saturating_sub(1);
+                        false
+                    }
+                    _ => false,
+                });
+                let end = end.unwrap_or(tokens.len());
+                (tokens[..end].to_vec(), Vec::new(), end < tokens.len())
+            }
+        };
+
+        // Find the innermost group enclosing each position, as the indices of its delimiters
+        let mut closes = vec![tokens.len(); tokens.len()];
+        let mut opened = Vec::new();
+        for (index, tok) in tokens.iter().enumerate() {
+            match tok {
+                Token::Delim('(', _) | Token::Delim('{', _) | Token::Delim('[', _) => opened.push(index),
+                Token::Delim(_, _) => {
+                    if let Some(open) = opened.pop() {
+                        closes[open] = index;
+                    }
+                }
+                _ => {}
+            }
+        }
+        let mut groups = Vec::with_capacity(tokens.len() + 1);
+        let mut depths = Vec::with_capacity(tokens.len() + 1);
+        opened.clear();
+        for index in 0..=tokens.len() {
+            groups.push(opened.last().map(|&open| (open, closes[open])));
+            depths.push(opened.len());
+            match tokens.get(index) {
+                Some(Token::Delim('(', _)) | Some(Token::Delim('{', _)) | Some(Token::Delim('[', _)) => opened.push(index),
+                Some(Token::Delim(_, _)) => {
+                    opened.pop();
+                }
+                _ => {}
+            }
+        }
+
+        let mut position = SpanPosition::default();
+        let mut positions = Vec::with_capacity(tokens.len() + 1);
+        positions.push(position.clone());
+        for tok in &tokens {
+            position.update(tok);
+            positions.push(position.clone());
+        }
+
+        self.tokens = Rc::new(tokens);
+        self.groups = Rc::new(groups);
+        self.positions = Rc::new(positions);
+        self.none_extents = Rc::new(none_extents);
+        self.depths = Rc::new(depths);
+        self.depth_exceeded = depth_exceeded;
+        self.index = 0;
+        self.consumed = 0;
+        self.pushback.clear();
+        self.pushback_span = None;
+    }
+}
+
+/// An iterator flattening the groups of a `TokenStream` into delimiter tokens.
+struct Flatten {
+    source_stack: Vec<(IntoIter, Option<Token>, Option<usize>)>,
+    emitted: usize,
+    none_extents: Vec<(Range<usize>, Span)>,
+    options: InputOptions,
+    depth_exceeded: bool,
+}
+
+impl Iterator for Flatten {
+    type Item = Token;
+
     fn next(&mut self) -> Option<Token> {
         if self.source_stack.is_empty() {
             return None;
         }
 
         while !self.source_stack.is_empty() {
-            let next = self.source_stack.last_mut().and_then(|(iter, _)| iter.next());
-            let next = match next {
-                Some(tt) => self.ungroup(tt),
-                None => None,
-            };
-            if let Some(tok) = next {
-                return Some(tok);
-            }
-            let (_empty, close) = self.source_stack.pop().unwrap();
-            if let Some(tok) = close {
-                return Some(tok);
+            let next = self.source_stack.last_mut().and_then(|(iter, _, _)| iter.next());
+            match next {
+                Some(tt) => {
+                    if let Some(tok) = self.ungroup(tt) {
+                        self.emitted += 1;
+                        return Some(tok);
+                    }
+                    if self.depth_exceeded {
+                        return None;
+                    }
+                    // Entered a none-delimited group, which has no delimiter tokens
+                }
+                None => {
+                    let (_empty, close, none_extent) = self.source_stack.pop().unwrap();
+                    if let Some(extent) = none_extent {
+                        self.none_extents[extent].0.end = self.emitted;
+                    }
+                    if let Some(tok) = close {
+                        self.emitted += 1;
+                        return Some(tok);
+                    }
+                }
             }
         }
 
         // We're finally at the end of input Bob...
         None
     }
+}
 
+impl Flatten {
     fn ungroup(&mut self, tt: TokenTree) -> Option<Token> {
         match tt {
             TokenTree::Punct(tok) => Some(Token::Punct(tok)),
             TokenTree::Ident(tok) => Some(Token::Ident(tok)),
             TokenTree::Literal(tok) => Some(Token::Literal(tok)),
+            TokenTree::Group(group) if self.options.shallow || (group.delimiter() == Delimiter::None && self.options.none_groups) => {
+                Some(Token::Group(group))
+            }
             TokenTree::Group(group) => {
+                if matches!(self.options.max_depth, Some(max_depth) if self.source_stack.len() > max_depth) {
+                    self.depth_exceeded = true;
+                    return None;
+                }
                 let (open, close) = match group.delimiter() {
                     Delimiter::Parenthesis => (Some('('), Some(')')),
                     Delimiter::Brace => (Some('{'), Some('}')),
                     Delimiter::Bracket => (Some('['), Some(']')),
                     Delimiter::None => (None, None),
                 };
+                let none_extent = if open.is_none() {
+                    self.none_extents.push((self.emitted..self.emitted, group.span()));
+                    Some(self.none_extents.len() - 1)
+                } else {
+                    None
+                };
                 self.source_stack.push((
                     group.stream().into_iter(),
-                    close.map(|c| Token::Delim(c, group.span())),
+                    close.map(|c| Token::Delim(c, span_close(&group))),
+                    none_extent,
                 ));
-                match open {
-                    Some(c) => Some(Token::Delim(c, group.span())),
-                    None => None,
-                }
+                open.map(|c| Token::Delim(c, span_open(&group)))
             }
         }
     }
 }
+
 impl From<TokenStreamBuiltin> for Input {
     fn from(stream: TokenStreamBuiltin) -> Input {
-        let stream: TokenStream = stream.to_string().parse().unwrap();
-        Input {
-            source_stack: vec![(stream.into_iter(), None)],
-            source_pos: 0,
-        }
+        Input::from(TokenStream::from(stream))
     }
 }
 impl From<TokenStream> for Input {
     fn from(stream: TokenStream) -> Input {
-        Input {
-            source_stack: vec![(stream.into_iter(), None)],
-            source_pos: 0,
-        }
+        Input::new(Source::Stream(stream), InputOptions::default(), None)
+    }
+}
+impl FromIterator<Token> for Input {
+    /// Creates an input from a sequence of tokens, such as ones synthesized in a test.
+    ///
+    /// The tokens are buffered as they are, even if their delimiters are unbalanced. So
+    /// `with_none_groups` and `shallow` have no effect on the input, while `with_max_depth`
+    /// still stops at the first group nested too deeply.
+    fn from_iter<T: IntoIterator<Item = Token>>(tokens: T) -> Input {
+        Input::new(Source::Tokens(Rc::new(tokens.into_iter().collect())), InputOptions::default(), None)
+    }
+}
+impl FromIterator<TokenTree> for Input {
+    fn from_iter<T: IntoIterator<Item = TokenTree>>(trees: T) -> Input {
+        Input::from(trees.into_iter().collect::<TokenStream>())
+    }
+}
+impl FromStr for Input {
+    type Err = LexError;
+
+    fn from_str(source: &str) -> Result<Input, LexError> {
+        TokenStream::from_str(source).map(Input::from)
     }
 }
 impl From<Input> for TokenStream {
+    /// Reconstructs the remaining tokens like `Input::to_token_stream`, except that a close
+    /// delimiter which doesn't match its open delimiter closes the group anyway (as the matching
+    /// delimiter), since the conversion can't fail.
     fn from(input: Input) -> TokenStream {
-        let mut rem = TokenStream::new();
-        for (source, close) in input.source_stack.into_iter().rev() {
-            rem.extend(source);
-            rem.extend(close.into_iter().map(|tok| TokenTree::try_from(tok).unwrap()));
+        tokens_to_stream(balance_delims(input.tokens(), true)).expect("delimiters are balanced")
+    }
+}
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.to_token_stream() {
+            Ok(stream) => write!(f, "{}", stream),
+            Err(_) => write_tokens(f, self.tokens()),
         }
-        rem
     }
 }
 
+impl Iterator for Input {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        let tok = match self.pushback.pop() {
+            Some(tok) => {
+                self.pushback_span = Some(tok.span());
+                tok
+            }
+            None => {
+                let tok = self.tokens.get(self.index)?.clone();
+                self.index += 1;
+                self.pushback_span = None;
+                tok
+            }
+        };
+        self.consumed += 1;
+        Some(tok)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len(), Some(self.len()))
+    }
+}
+
+impl ExactSizeIterator for Input {}
+
 impl StreamOnce for Input {
     type Item = Token;
-    type Range =  Self::Item;
-    type Position = usize;
+    type Range = TokenSlice;
+    type Position = SpanPosition;
     type Error = Error<Self::Item, Self::Range>;
 
     fn uncons(&mut self) -> Result<Self::Item, StreamErrorFor<Self>> {
         match self.next() {
+            Some(tok) => Ok(tok),
+            None if self.depth_exceeded => Err(Error::Message("maximum nesting depth exceeded".into())),
             None => Err(Error::end_of_input()),
-            Some(tok) => {
-                self.source_pos += 1;
-                Ok(tok)
-            }
         }
     }
 
@@ -127,9 +535,96 @@ impl StreamOnce for Input {
     }
 }
 
+/// A checkpoint of an `Input`, including any tokens pushed back with `Input::push_front`.
+#[derive(Clone)]
+pub struct InputCheckpoint {
+    index: usize,
+    consumed: usize,
+    pushback: Vec<Token>,
+    pushback_span: Option<Span>,
+}
+
+impl Resetable for Input {
+    type Checkpoint = InputCheckpoint;
+
+    fn checkpoint(&self) -> Self::Checkpoint {
+        InputCheckpoint {
+            index: self.index,
+            consumed: self.consumed,
+            pushback: self.pushback.clone(),
+            pushback_span: self.pushback_span,
+        }
+    }
+
+    fn reset(&mut self, checkpoint: Self::Checkpoint) {
+        self.index = checkpoint.index;
+        self.consumed = checkpoint.consumed;
+        self.pushback = checkpoint.pushback;
+        self.pushback_span = checkpoint.pushback_span;
+    }
+}
+
+impl RangeStreamOnce for Input {
+    fn uncons_range(&mut self, size: usize) -> Result<Self::Range, StreamErrorFor<Self>> {
+        if size > self.len() {
+            return Err(Error::end_of_input());
+        }
+        if !self.pushback.is_empty() {
+            return Ok(TokenSlice::from(self.by_ref().take(size).collect::<Vec<_>>()));
+        }
+        let start = self.index;
+        self.index += size;
+        self.consumed += size;
+        if size > 0 {
+            self.pushback_span = None;
+        }
+        Ok(TokenSlice { tokens: self.tokens.clone(), start, end: self.index })
+    }
+
+    fn uncons_while<F>(&mut self, mut predicate: F) -> Result<Self::Range, StreamErrorFor<Self>>
+    where
+        F: FnMut(Self::Item) -> bool,
+    {
+        if !self.pushback.is_empty() {
+            let mut tokens = Vec::new();
+            while matches!(self.peek(), Some(tok) if predicate(tok.clone())) {
+                tokens.extend(self.next());
+            }
+            return Ok(TokenSlice::from(tokens));
+        }
+        let start = self.index;
+        while matches!(self.tokens.get(self.index), Some(tok) if predicate(tok.clone())) {
+            self.index += 1;
+            self.consumed += 1;
+            self.pushback_span = None;
+        }
+        Ok(TokenSlice { tokens: self.tokens.clone(), start, end: self.index })
+    }
+
+    fn distance(&self, end: &Self::Checkpoint) -> usize {
+        self.consumed - end.consumed
+    }
+}
+
+impl FullRangeStream for Input {
+    fn range(&self) -> Self::Range {
+        if !self.pushback.is_empty() {
+            return TokenSlice::from(self.tokens().cloned().collect::<Vec<_>>());
+        }
+        TokenSlice { tokens: self.tokens.clone(), start: self.index, end: self.tokens.len() }
+    }
+}
+
 impl Positioned for Input {
     fn position(&self) -> Self::Position {
-        self.source_pos
+        let mut position = self.positions[self.index].clone();
+        position.pos = self.consumed;
+        if let Some(span) = self.pushback_span {
+            position.span = span;
+        } else if self.index == self.tokens.len() && self.pushback.is_empty() {
+            position.span = self.eof_span();
+        }
+        position
     }
 }
 
@@ -137,27 +632,276 @@ impl DefaultPositioned for Input {
     type Positioner = SpanPosition;
 }
 
+/// A stream which can create an `Input` over the tokens inside a group, with the stream's options.
+///
+/// This is how `parser::nested` keeps the options of the input it parses from. It's implemented
+/// for `Input` and the `easy::Stream` and `State` wrappers of it, but not for the `BufferedStream`
+/// of `Input::with_lookahead` (which `Input` doesn't need to backtrack).
+pub trait GroupInput {
+    /// Returns a new input over only the tokens inside `group` (see `Input::enter_group`).
+    fn enter_group(&self, group: &Group) -> Input;
+}
+
+impl GroupInput for Input {
+    fn enter_group(&self, group: &Group) -> Input {
+        Input::enter_group(self, group)
+    }
+}
+
+impl<S: GroupInput> GroupInput for easy::Stream<S> {
+    fn enter_group(&self, group: &Group) -> Input {
+        self.0.enter_group(group)
+    }
+}
+
+impl<S: GroupInput, X> GroupInput for State<S, X> {
+    fn enter_group(&self, group: &Group) -> Input {
+        self.input.enter_group(group)
+    }
+}
+
+/// The inputs of a `#[proc_macro_attribute]`: the attribute's arguments and the item it's applied to.
+///
+/// A common attribute macro parses its arguments with a grammar and passes the item through
+/// (possibly alongside generated code), which `parse_args` and `item_stream` help with.
+///
+/// ```rust,ignore
+/// #[proc_macro_attribute]
+/// pub fn route(args: TokenStream, item: TokenStream) -> TokenStream {
+///     let input = AttributeInput::new(args, item);
+///     let route = match input.parse_args(route_grammar()) {
+///         Ok(route) => route,
+///         Err(err) => panic!("error parsing arguments of `route` attribute: {}", err),
+///     };
+///     impl_route(&route, input.item_stream())  // e.g. using the `quote` crate
+/// }
+/// ```
+#[derive(Clone)]
+pub struct AttributeInput {
+    pub args: Input,
+    pub item: Input,
+    item_stream: TokenStream,
+}
+
+impl AttributeInput {
+    /// Creates the inputs from the two `TokenStream`s an attribute macro receives.
+    pub fn new<T: Into<TokenStream>>(args: T, item: T) -> AttributeInput {
+        let item_stream: TokenStream = item.into();
+        AttributeInput { args: Input::from(args.into()), item: Input::from(item_stream.clone()), item_stream }
+    }
+
+    /// Parses the attribute's arguments with `parser`, which must consume all of them.
+    pub fn parse_args<P>(&self, parser: P) -> Result<P::Output, easy::Errors<Token, TokenSlice, SpanPosition>>
+    where
+        P: Parser<Input = easy::Stream<Input>>,
+    {
+        (parser, eof()).map(|(output, _)| output).easy_parse(self.args.clone()).map(|(output, _)| output)
+    }
+
+    /// Returns the tokens of the item as they were received, for passing the item through.
+    ///
+    /// Unlike `item.to_token_stream()`, this includes any tokens of the item already consumed
+    /// and keeps `Delimiter::None` groups intact.
+    pub fn item_stream(&self) -> TokenStream {
+        self.item_stream.clone()
+    }
+}
+
+/// The position of the input after consuming a token, along with the span of that token.
+///
+/// The position also records the path of groups the token is nested in, so errors can say
+/// where in a nested input they occurred (e.g. "2nd `{` group → 3rd `(` group → token 4").
 #[derive(Clone, Debug)]
 pub struct SpanPosition {
     pos: usize,
     span: Span,
+    path: Rc<Vec<(char, usize)>>,
+    offset: usize,
 }
 
 impl SpanPosition {
+    /// Returns the number of tokens consumed before this position.
+    ///
+    /// This is the index of the next token in the input's buffer, unless tokens were pushed
+    /// back with `Input::push_front` (which are counted when they are consumed).
+    pub fn index(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns the groups enclosing this position, outermost first.
+    ///
+    /// Each group is given as its open delimiter and its 1-based index among the token trees
+    /// of the group enclosing it (where a nested group counts as a single tree).
+    pub fn group_path(&self) -> &[(char, usize)] {
+        &self.path
+    }
+
+    /// Returns the 1-based index of the most recently consumed token tree within its group,
+    /// or `0` at the start of a group (or of the input).
+    pub fn offset_in_group(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the span of the most recently consumed token.
     pub fn into_span(&self) -> Span {
         self.span
     }
 }
 
+impl From<SpanPosition> for Span {
+    fn from(position: SpanPosition) -> Span {
+        position.span
+    }
+}
+
+/// Joins two spans into one covering both, where supported.
+///
+/// `Span::join` is only available with `--cfg procmacro2_semver_exempt`,
+/// otherwise this falls back to the span of the first token.
+pub(crate) fn join_spans(first: Span, last: Span) -> Span {
+    #[cfg(procmacro2_semver_exempt)]
+    {
+        first.join(last).unwrap_or(first)
+    }
+    #[cfg(not(procmacro2_semver_exempt))]
+    {
+        let _ = last;
+        first
+    }
+}
+
+/// Returns the span of the open delimiter of `group`, where supported.
+///
+/// `Group::span_open` is only available with `--cfg procmacro2_semver_exempt`, otherwise this
+/// falls back to the span of the whole group.
+fn span_open(group: &Group) -> Span {
+    #[cfg(procmacro2_semver_exempt)]
+    {
+        group.span_open()
+    }
+    #[cfg(not(procmacro2_semver_exempt))]
+    {
+        group.span()
+    }
+}
+
+/// Returns the span of the close delimiter of `group`, where supported (see `span_open`).
+fn span_close(group: &Group) -> Span {
+    #[cfg(procmacro2_semver_exempt)]
+    {
+        group.span_close()
+    }
+    #[cfg(not(procmacro2_semver_exempt))]
+    {
+        group.span()
+    }
+}
+
+/// Calls `f` with the text of `ident` (e.g. to compare it with a keyword).
+///
+/// Unlike `ident.to_string()` this doesn't allocate for idents of up to 32 bytes, which keeps
+/// keyword matching cheap when trying many alternatives.
+pub(crate) fn with_ident_str<R, F>(ident: &Ident, f: F) -> R
+where
+    F: FnOnce(&str) -> R,
+{
+    struct Buffer {
+        bytes: [u8; 32],
+        len: usize,
+    }
+
+    impl fmt::Write for Buffer {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            let end = self.len + s.len();
+            self.bytes.get_mut(self.len..end).ok_or(fmt::Error)?.copy_from_slice(s.as_bytes());
+            self.len = end;
+            Ok(())
+        }
+    }
+
+    let mut buffer = Buffer { bytes: [0; 32], len: 0 };
+    match fmt::Write::write_fmt(&mut buffer, format_args!("{}", ident)) {
+        Ok(()) => f(std::str::from_utf8(&buffer.bytes[..buffer.len]).expect("written as `str`s")),
+        Err(_) => f(&ident.to_string()),
+    }
+}
+
+/// Returns whether `value` formats exactly as `text`, without allocating a `String` for it.
+pub(crate) fn display_eq<T: fmt::Display>(value: &T, text: &str) -> bool {
+    struct Matcher<'a> {
+        rest: &'a str,
+    }
+
+    impl<'a> fmt::Write for Matcher<'a> {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.rest = self.rest.strip_prefix(s).ok_or(fmt::Error)?;
+            Ok(())
+        }
+    }
+
+    let mut matcher = Matcher { rest: text };
+    fmt::Write::write_fmt(&mut matcher, format_args!("{}", value)).is_ok() && matcher.rest.is_empty()
+}
+
+/// Returns whether there is whitespace between `first` and the following `next`.
+///
+/// Source locations are only available with `--cfg procmacro2_semver_exempt`, otherwise
+/// whitespace can't be detected and this always returns `false`.
+pub(crate) fn whitespace_between(first: Span, next: Span) -> bool {
+    #[cfg(procmacro2_semver_exempt)]
+    {
+        first.end() != next.start()
+    }
+    #[cfg(not(procmacro2_semver_exempt))]
+    {
+        let _ = (first, next);
+        false
+    }
+}
+
 impl Default for SpanPosition {
     fn default() -> Self {
         SpanPosition {
             pos: 0,
-            span: Span::call_site()
+            span: Span::call_site(),
+            path: Rc::new(Vec::new()),
+            offset: 0,
+        }
+    }
+}
+
+impl fmt::Display for SpanPosition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.path.is_empty() && self.offset == 0 {
+            return write!(f, "start of input");
+        }
+        for &(delim, index) in self.path.iter() {
+            let name = match delim {
+                '(' => "paren",
+                '[' => "bracket",
+                _ => "brace",
+            };
+            write!(f, "{} {} group → ", ordinal(index), name)?;
+        }
+        match self.offset {
+            0 => write!(f, "start"),
+            offset => write!(f, "token {}", offset),
         }
     }
 }
 
+/// Formats `n` as an English ordinal (e.g. `1st`, `12th`, or `23rd`).
+fn ordinal(n: usize) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", n, suffix)
+}
+
 impl PartialOrd for SpanPosition {
     fn partial_cmp(&self, other: &SpanPosition) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -190,18 +934,65 @@ impl Positioner<Token> for SpanPosition {
     fn update(&mut self, item: &Token) {
         self.pos += 1;
         self.span = item.span();
+        match item {
+            Token::Delim(delim @ '(', _) | Token::Delim(delim @ '{', _) | Token::Delim(delim @ '[', _) => {
+                Rc::make_mut(&mut self.path).push((*delim, self.offset + 1));
+                self.offset = 0;
+            }
+            Token::Delim(_, _) => {
+                if let Some((_, index)) = Rc::make_mut(&mut self.path).pop() {
+                    self.offset = index;
+                }
+            }
+            _ => self.offset += 1,
+        }
     }
 }
 
 #[derive(Clone, Debug)]
 pub enum Token {
+    /// An open or close delimiter of a flattened group, with the span of just that delimiter
+    /// where supported (see `Group::span_open`), or else the span of the whole group.
     Delim(char, Span),
     Punct(Punct),
     Ident(Ident),
     Literal(Literal),
+    /// A group kept whole, only produced by `Input::shallow` (or by `Input::with_none_groups`
+    /// for `Delimiter::None` groups).
+    Group(Group),
 }
 
 impl Token {
+    /// Creates an ident token (e.g. `Token::ident("name", Span::call_site())`).
+    ///
+    /// Panics if `name` is not a valid ident, like `Ident::new`.
+    pub fn ident(name: &str, span: Span) -> Token {
+        Token::Ident(Ident::new(name, span))
+    }
+
+    /// Creates a punctuation token (e.g. `Token::punct('+', Spacing::Alone, Span::call_site())`).
+    ///
+    /// Panics if `ch` is not a valid punctuation char, like `Punct::new`.
+    pub fn punct(ch: char, spacing: Spacing, span: Span) -> Token {
+        let mut punct = Punct::new(ch, spacing);
+        punct.set_span(span);
+        Token::Punct(punct)
+    }
+
+    /// Creates a literal token from `lit` (e.g. `Literal::string("x")`) with the given span.
+    pub fn literal(mut lit: Literal, span: Span) -> Token {
+        lit.set_span(span);
+        Token::Literal(lit)
+    }
+
+    /// Creates a delimiter token (e.g. `Token::delim('{', Span::call_site())`).
+    ///
+    /// Panics if `ch` is not one of `(`, `)`, `[`, `]`, `{` or `}`.
+    pub fn delim(ch: char, span: Span) -> Token {
+        assert!("()[]{}".contains(ch), "`{}` is not a delimiter", ch);
+        Token::Delim(ch, span)
+    }
+
     pub fn to_char(&self) -> Option<char> {
         match self {
             Token::Delim(ch, _) => Some(*ch),
@@ -212,10 +1003,19 @@ impl Token {
 
     pub fn span(&self) -> Span {
         match self {
-            Token::Delim(_, span) => span.clone(),
+            Token::Delim(_, span) => *span,
             Token::Punct(tok) => tok.span(),
             Token::Ident(tok) => tok.span(),
             Token::Literal(tok) => tok.span(),
+            Token::Group(tok) => tok.span(),
+        }
+    }
+
+    /// Compares two tokens like `==`, but puncts must also have the same `Spacing` to be equal.
+    pub fn eq_spacing(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Token::Punct(l), Token::Punct(r)) => l.as_char() == r.as_char() && l.spacing() == r.spacing(),
+            _ => self == other,
         }
     }
 }
@@ -225,11 +1025,277 @@ impl PartialEq for Token {
         match (self, other) {
             (Token::Delim(l, _), Token::Delim(r, _)) => l == r,
             (Token::Punct(l), Token::Punct(r)) => l.as_char() == r.as_char(),
-            (Token::Ident(l), Token::Ident(r)) => l.to_string() == r.to_string(),
+            (Token::Ident(l), Token::Ident(r)) => l == r,
             (Token::Literal(l), Token::Literal(r)) => l.to_string() == r.to_string(),
+            (Token::Group(l), Token::Group(r)) => l.delimiter() == r.delimiter() && l.stream().to_string() == r.stream().to_string(),
+            _ => false,
+        }
+    }
+}
+
+impl From<Ident> for Token {
+    fn from(tok: Ident) -> Token {
+        Token::Ident(tok)
+    }
+}
+
+impl From<Punct> for Token {
+    fn from(tok: Punct) -> Token {
+        Token::Punct(tok)
+    }
+}
+
+impl From<Literal> for Token {
+    fn from(tok: Literal) -> Token {
+        Token::Literal(tok)
+    }
+}
+
+impl Eq for Token {}
+
+impl Hash for Token {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Hashes the same parts of the token as `PartialEq` compares (i.e. ignoring spans)
+        match self {
+            Token::Delim(ch, _) => (0u8, ch).hash(state),
+            Token::Punct(tok) => (1u8, tok.as_char()).hash(state),
+            Token::Ident(tok) => (2u8, tok).hash(state),
+            Token::Literal(tok) => (3u8, tok.to_string()).hash(state),
+            Token::Group(tok) => {
+                let delimiter = match tok.delimiter() {
+                    Delimiter::Parenthesis => '(',
+                    Delimiter::Brace => '{',
+                    Delimiter::Bracket => '[',
+                    Delimiter::None => ' ',
+                };
+                (4u8, delimiter, tok.stream().to_string()).hash(state)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Delim(ch, _) => write!(f, "{}", ch),
+            Token::Punct(tok) => write!(f, "{}", tok.as_char()),
+            Token::Ident(tok) => write!(f, "{}", tok),
+            Token::Literal(tok) => write!(f, "{}", tok),
+            Token::Group(tok) => write!(f, "{}", tok),
+        }
+    }
+}
+
+/// Writes `tokens` separated by spaces, except after joint punctuation.
+///
+/// Unlike the `Display` of a `TokenStream`, this can write unbalanced delimiters.
+pub(crate) fn write_tokens<'a, T>(f: &mut fmt::Formatter, tokens: T) -> fmt::Result
+where
+    T: IntoIterator<Item = &'a Token>,
+{
+    let mut joint = true;
+    for tok in tokens {
+        if !joint {
+            write!(f, " ")?;
+        }
+        write!(f, "{}", tok)?;
+        joint = match tok {
+            Token::Punct(punct) => punct.spacing() == Spacing::Joint,
             _ => false,
+        };
+    }
+    Ok(())
+}
+
+/// Returns the next token of any token stream without consuming it, or `None` at the end of input.
+///
+/// Unlike `Input::peek`, this works with the generic `input` of a custom `Parser` or a
+/// `parser!(fn name(input: &mut Input) ...)` function.
+pub fn peek<I>(input: &mut I) -> Option<Token>
+where
+    I: Stream<Item = Token>,
+{
+    peek_n(input, 1).pop()
+}
+
+/// Returns the next `k` tokens of any token stream without consuming them (fewer near the end
+/// of input), like `peek`.
+///
+/// A buffered stream (see `Input::with_lookahead`) must support a lookahead of at least `k`.
+pub fn peek_n<I>(input: &mut I, k: usize) -> Vec<Token>
+where
+    I: Stream<Item = Token>,
+{
+    let checkpoint = input.checkpoint();
+    let mut tokens = Vec::with_capacity(k);
+    while tokens.len() < k {
+        match input.uncons() {
+            Ok(tok) => tokens.push(tok),
+            Err(_) => break,
         }
     }
+    input.reset(checkpoint);
+    tokens
+}
+
+/// A range of consecutive tokens of an `Input`, sharing the input's token buffer.
+///
+/// This is the `Range` of `Input`, produced by range parsers such as
+/// `combine::parser::range::take_while` and `recognize`.
+#[derive(Clone)]
+pub struct TokenSlice {
+    tokens: Rc<Vec<Token>>,
+    start: usize,
+    end: usize,
+}
+
+impl Deref for TokenSlice {
+    type Target = [Token];
+
+    fn deref(&self) -> &[Token] {
+        &self.tokens[self.start..self.end]
+    }
+}
+
+impl combine::stream::Range for TokenSlice {
+    fn len(&self) -> usize {
+        self.end - self.start
+    }
+}
+
+impl From<Vec<Token>> for TokenSlice {
+    fn from(tokens: Vec<Token>) -> TokenSlice {
+        let end = tokens.len();
+        TokenSlice { tokens: Rc::new(tokens), start: 0, end }
+    }
+}
+
+impl PartialEq for TokenSlice {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl fmt::Debug for TokenSlice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl fmt::Display for TokenSlice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, tok) in self.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", tok)?;
+        }
+        Ok(())
+    }
+}
+
+/// Rebuilds a `TokenStream` from a flattened sequence of tokens, pairing delimiters back into groups.
+///
+/// This is the inverse of flattening a `TokenStream` into an `Input` (e.g. for the tokens consumed
+/// by a parser). Unlike converting each token with `TokenTree::try_from`, delimiter tokens aren't
+/// dropped. Fails if the delimiters in `tokens` are unbalanced.
+pub fn tokens_to_stream<T>(tokens: T) -> Result<TokenStream, UnbalancedDelimError>
+where
+    T: IntoIterator<Item = Token>,
+{
+    let mut opened: Vec<(char, Span)> = Vec::new();
+    let mut trees: Vec<Vec<TokenTree>> = vec![Vec::new()];
+    for tok in tokens {
+        match tok {
+            Token::Delim(ch @ '(', span) | Token::Delim(ch @ '{', span) | Token::Delim(ch @ '[', span) => {
+                opened.push((ch, span));
+                trees.push(Vec::new());
+            }
+            Token::Delim(close, close_span) => match opened.pop() {
+                Some((open @ '(', span)) if close == ')' => close_group(&mut trees, open, join_spans(span, close_span)),
+                Some((open @ '{', span)) if close == '}' => close_group(&mut trees, open, join_spans(span, close_span)),
+                Some((open @ '[', span)) if close == ']' => close_group(&mut trees, open, join_spans(span, close_span)),
+                _ => return Err(UnbalancedDelimError { delim: close, span: close_span }),
+            },
+            tok => trees.last_mut().unwrap().extend(TokenTree::try_from(tok)),
+        }
+    }
+    if let Some(&(open, span)) = opened.last() {
+        return Err(UnbalancedDelimError { delim: open, span });
+    }
+    Ok(trees.pop().unwrap().into_iter().collect())
+}
+
+/// Skips the close delimiters without an open delimiter in `tokens` (e.g. the closes of the
+/// groups enclosing the rest of an input) and closes the groups which aren't closed at the end,
+/// so the rest of an input can be rebuilt with `tokens_to_stream`.
+///
+/// If `repair` is true, a close delimiter which doesn't match its open delimiter is replaced
+/// by the one which does.
+fn balance_delims<'a, T>(tokens: T, repair: bool) -> Vec<Token>
+where
+    T: IntoIterator<Item = &'a Token>,
+{
+    let mut opened = Vec::new();
+    let mut balanced = Vec::new();
+    for tok in tokens {
+        match *tok {
+            Token::Delim('(', span) => opened.push((')', span)),
+            Token::Delim('{', span) => opened.push(('}', span)),
+            Token::Delim('[', span) => opened.push((']', span)),
+            Token::Delim(_, span) => match opened.pop() {
+                Some((close, _)) if repair => {
+                    balanced.push(Token::Delim(close, span));
+                    continue;
+                }
+                Some(_) => {}
+                None => continue,
+            },
+            _ => {}
+        }
+        balanced.push(tok.clone());
+    }
+    balanced.extend(opened.into_iter().rev().map(|(close, span)| Token::Delim(close, span)));
+    balanced
+}
+
+/// The error returned by `tokens_to_stream` when the delimiters of the tokens don't pair up.
+#[derive(Clone, Debug)]
+pub struct UnbalancedDelimError {
+    delim: char,
+    span: Span,
+}
+
+impl UnbalancedDelimError {
+    /// Returns the delimiter which has no matching delimiter (e.g. `'}'`).
+    pub fn delim(&self) -> char {
+        self.delim
+    }
+
+    /// Returns the span of the unmatched delimiter.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl fmt::Display for UnbalancedDelimError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unbalanced delimiter `{}`", self.delim)
+    }
+}
+
+impl std::error::Error for UnbalancedDelimError {}
+
+/// Wraps the innermost trees being collected by `tokens_to_stream` into a group opened by `open`.
+fn close_group(trees: &mut Vec<Vec<TokenTree>>, open: char, span: Span) {
+    let delimiter = match open {
+        '(' => Delimiter::Parenthesis,
+        '{' => Delimiter::Brace,
+        _ => Delimiter::Bracket,
+    };
+    let mut group = Group::new(delimiter, trees.pop().unwrap().into_iter().collect());
+    group.set_span(span);
+    trees.last_mut().unwrap().push(TokenTree::Group(group));
 }
 
 impl TryFrom<Token> for TokenTree {
@@ -241,6 +1307,218 @@ impl TryFrom<Token> for TokenTree {
             Token::Punct(tok) => Ok(TokenTree::Punct(tok)),
             Token::Ident(tok) => Ok(TokenTree::Ident(tok)),
             Token::Literal(tok) => Ok(TokenTree::Literal(tok)),
+            Token::Group(tok) => Ok(TokenTree::Group(tok)),
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::{span_close, Input, Token};
+    use crate::parser::{ident, recognize, spanned};
+    use combine::{Parser, Positioned};
+    use combine::stream::{Resetable, StreamOnce};
+    use proc_macro2::{Delimiter, Group, Span, TokenStream, TokenTree};
+
+    fn texts(input: Input) -> Vec<String> {
+        input.map(|tok| tok.to_string()).collect()
+    }
+
+    /// Returns the tokens `x * [1 + 2] + y`, where `[..]` is a `Delimiter::None` group.
+    fn with_none_group() -> TokenStream {
+        let mut stream: TokenStream = "x *".parse().unwrap();
+        let group = Group::new(Delimiter::None, "1 + 2".parse().unwrap());
+        stream.extend(vec![TokenTree::Group(group)]);
+        stream.extend("+ y".parse::<TokenStream>().unwrap());
+        stream
+    }
+
+    fn collect(tokens: &[(char, &str)]) -> Input {
+        let span = Span::call_site();
+        tokens.iter().map(|&(kind, text)| match kind {
+            'd' => Token::delim(text.chars().next().unwrap(), span),
+            _ => Token::ident(text, span),
+        }).collect()
+    }
+
+    #[test]
+    fn collect_unbalanced_tokens() {
+        let input = collect(&[('d', "("), ('i', "a"), ('d', "]")]);
+        assert_eq!(input.len(), 3);
+        assert!(input.to_token_stream().is_err());
+        assert_eq!(input.to_string(), "( a ]");
+        assert_eq!(TokenStream::from(input).to_string(), "( a )");
+
+        let input = collect(&[('i', "a"), ('d', "}"), ('d', "(")]);
+        assert_eq!(input.len(), 3);
+        assert_eq!(input.to_token_stream().unwrap().to_string(), "a ( )");
+    }
+
+    #[test]
+    #[should_panic(expected = "`<` is not a delimiter")]
+    fn delim_rejects_other_chars() {
+        Token::delim('<', Span::call_site());
+    }
+
+    #[test]
+    fn collect_with_max_depth() {
+        let input = collect(&[('d', "{"), ('d', "("), ('i', "a"), ('d', ")"), ('d', "}")]).with_max_depth(1);
+        assert_eq!(input.len(), 1);
+    }
+
+    #[test]
+    fn to_token_stream_skips_entered_groups() {
+        let mut input = Input::parse_str("{ a ( b ) c }").unwrap();
+        input.nth(1);
+        assert_eq!(input.to_token_stream().unwrap().to_string(), "( b ) c");
+        assert_eq!(TokenStream::from(input).to_string(), "( b ) c");
+    }
+
+    #[test]
+    fn push_front_positions() {
+        let mut input = Input::parse_str("b c").unwrap();
+        input.push_front(Token::ident("a", Span::call_site()));
+        let start = input.position();
+        let checkpoint = input.checkpoint();
+        assert_eq!(input.uncons().map(|tok| tok.to_string()), Ok("a".to_string()));
+        assert!(input.position() > start);
+        assert_eq!(input.position().index(), 1);
+        input.uncons().unwrap();
+        assert_eq!(input.position().index(), 2);
+        input.reset(checkpoint);
+        assert_eq!(input.position(), start);
+        assert_eq!(input.len(), 3);
+    }
+
+    #[test]
+    fn push_front_recognize() {
+        let mut input = Input::parse_str("b c").unwrap();
+        input.push_front(Token::ident("a", Span::call_site()));
+        let (stream, rest) = recognize(ident()).parse(input.clone()).unwrap();
+        assert_eq!(stream.to_string(), "a");
+        assert_eq!(rest.len(), 2);
+        let (stream, rest) = recognize((ident(), ident())).parse(input.clone()).unwrap();
+        assert_eq!(stream.to_string(), "a b");
+        assert_eq!(rest.len(), 1);
+        let ((name, _), rest) = spanned(ident()).parse(input).unwrap();
+        assert_eq!(name, "a");
+        assert_eq!(rest.len(), 2);
+    }
+
+    #[test]
+    fn uncons_checkpoint_reset() {
+        let mut input = Input::parse_str("a { b } c").unwrap();
+        assert_eq!(input.len(), 5);
+        assert_eq!(input.uncons().map(|tok| tok.to_string()), Ok("a".to_string()));
+        let checkpoint = input.checkpoint();
+        let position = input.position();
+        assert_eq!(texts(input.clone()), ["{", "b", "}", "c"]);
+        input.nth(3);
+        assert!(input.uncons().is_err());
+        assert_eq!(input.position().index(), 5);
+        input.reset(checkpoint);
+        assert_eq!(input.position(), position);
+        assert_eq!(input.peek().map(ToString::to_string), Some("{".to_string()));
+        assert_eq!(input.peek_n(2).len(), 2);
+    }
+
+    #[test]
+    fn depth_and_current_delimiter() {
+        let mut input = Input::parse_str("a { b ( c ) } d").unwrap();
+        let mut seen = Vec::new();
+        loop {
+            seen.push((input.depth(), input.current_delimiter()));
+            if input.uncons().is_err() {
+                break;
+            }
+        }
+        let (paren, brace) = (Some(Delimiter::Parenthesis), Some(Delimiter::Brace));
+        assert_eq!(seen, [
+            (0, None), (0, None), (1, brace), (1, brace), (2, paren),
+            (2, paren), (1, brace), (0, None), (0, None),
+        ]);
+    }
+
+    #[test]
+    fn max_depth_exceeded_is_an_error() {
+        let mut input = Input::parse_str("a ( b [ c ] ) d").unwrap().with_max_depth(1);
+        assert_eq!(texts(input.clone()), ["a", "(", "b"]);
+        input.nth(2);
+        let err = input.uncons().unwrap_err();
+        assert_eq!(err.to_string(), "maximum nesting depth exceeded");
+
+        let input = Input::parse_str("a ( b [ c ] ) d").unwrap().with_max_depth(2);
+        assert_eq!(input.len(), 8);
+    }
+
+    #[test]
+    fn none_groups_are_flattened_by_default() {
+        let input = Input::from(with_none_group());
+        assert_eq!(texts(input.clone()), ["x", "*", "1", "+", "2", "+", "y"]);
+        let extents = input.none_group_extents();
+        assert_eq!(extents.len(), 1);
+        assert_eq!(extents[0].0, 2..5);
+    }
+
+    #[test]
+    fn with_none_groups_keeps_groups() {
+        let input = Input::from(with_none_group()).with_none_groups();
+        let tokens: Vec<Token> = input.clone().collect();
+        assert_eq!(tokens.len(), 5);
+        match &tokens[2] {
+            Token::Group(group) => assert_eq!(group.delimiter(), Delimiter::None),
+            tok => panic!("expected a group, found {}", tok),
+        }
+        assert!(input.none_group_extents().is_empty());
+    }
+
+    #[test]
+    fn shallow_keeps_all_groups() {
+        let input = Input::parse_str("a { b ( c ) } [ d ]").unwrap().shallow();
+        let tokens: Vec<Token> = input.collect();
+        assert_eq!(tokens.len(), 3);
+        assert!(tokens[1..].iter().all(|tok| matches!(tok, Token::Group(_))));
+    }
+
+    #[test]
+    fn enter_group_keeps_options() {
+        let stream: TokenStream = "{ a ( b [ c ] ) }".parse().unwrap();
+        let group = match stream.into_iter().next() {
+            Some(TokenTree::Group(group)) => group,
+            _ => unreachable!(),
+        };
+        let input = Input::from(TokenStream::new()).with_max_depth(1);
+        let mut inner = input.enter_group(&group);
+        assert_eq!(texts(inner.clone()), ["a", "(", "b"]);
+        let last = inner.nth(2).unwrap();
+        assert!(inner.uncons().is_err());
+        assert_eq!(format!("{:?}", inner.eof_span()), format!("{:?}", last.span()));
+    }
+
+    #[test]
+    fn eof_position_is_last_token() {
+        let groups: Vec<Group> = "{ a b } { }".parse::<TokenStream>().unwrap().into_iter().filter_map(|tree| match tree {
+            TokenTree::Group(group) => Some(group),
+            _ => None,
+        }).collect();
+        let mut inner = Input::from(TokenStream::new()).enter_group(&groups[0]);
+        let last = inner.nth(1).unwrap();
+        assert_eq!(format!("{:?}", inner.position().into_span()), format!("{:?}", last.span()));
+        let empty = Input::from(TokenStream::new()).enter_group(&groups[1]);
+        assert_eq!(format!("{:?}", empty.position().into_span()), format!("{:?}", span_close(&groups[1])));
+    }
+
+    #[test]
+    fn positions_record_group_path() {
+        let mut input = Input::parse_str("a { b ( c d ) e } f").unwrap();
+        assert_eq!(input.position().to_string(), "start of input");
+        input.nth(5);
+        let position = input.position();
+        assert_eq!(position.group_path(), &[('{', 2), ('(', 2)]);
+        assert_eq!(position.offset_in_group(), 2);
+        assert_eq!(position.to_string(), "2nd brace group → 2nd paren group → token 2");
+        input.next();
+        assert_eq!(input.position().to_string(), "2nd brace group → token 2");
+        input.next();
+        assert_eq!(input.position().to_string(), "2nd brace group → token 3");
+    }
+}
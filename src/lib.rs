@@ -94,6 +94,23 @@
 //! #
 //! # fn impl_hello_macro(ast: &Literal) -> TokenStream { unimplemented!() }
 //! ```
+//!
+//! ## Implementing an attribute macro
+//! An attribute macro receives both the attribute's arguments and the item it's applied to,
+//! which `AttributeInput` pairs up. Typically only the arguments are parsed with a grammar.
+//!
+//! ```rust,ignore
+//! #[proc_macro_attribute]
+//! pub fn hello_attribute(args: TokenStream, item: TokenStream) -> TokenStream {
+//!     let input = AttributeInput::new(args, item);
+//!     let ast = match input.parse_args(hello_grammar()) {
+//!         Ok(ast) => ast,
+//!         Err(err) => panic!("error parsing in `hello_attribute` attribute: {}", err),
+//!     };
+//!
+//!     impl_hello_attribute(&ast, input.item_stream())
+//! }
+//! ```
 
 extern crate proc_macro;
 extern crate proc_macro2;
@@ -101,8 +118,10 @@ extern crate proc_macro2;
 mod boilerplate;
 pub mod diagnostic;
 pub mod input;
+pub mod literal;
 pub mod parser;
+pub mod syntax;
 
 pub use diagnostic::Incomplete;
-pub use input::{Input, Token};
-pub use proc_macro2::{Ident, Literal, Punct};
\ No newline at end of file
+pub use input::{AttributeInput, Input, Token};
+pub use proc_macro2::{Ident, Literal, Punct, Span};
\ No newline at end of file
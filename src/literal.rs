@@ -0,0 +1,396 @@
+//! Decoding of literal tokens into the values they represent.
+
+use proc_macro2::Literal;
+use std::str::Chars;
+
+/// The decoded value of a literal token.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Str(String),
+    ByteStr(Vec<u8>),
+    Byte(u8),
+    Char(char),
+    Int(u128, Option<String>),
+    Float(f64),
+}
+
+/// Decodes any kind of literal into its value.
+///
+/// Returns `None` if the literal is malformed or its value can't be represented (e.g. an
+/// integer larger than `u128::MAX`).
+pub fn parse(lit: &Literal) -> Option<Value> {
+    parse_string(lit).map(Value::Str)
+        .or_else(|| parse_byte_string(lit).map(Value::ByteStr))
+        .or_else(|| parse_byte(lit).map(Value::Byte))
+        .or_else(|| parse_char(lit).map(Value::Char))
+        .or_else(|| parse_int(lit).map(|(value, suffix)| Value::Int(value, suffix)))
+        .or_else(|| parse_float(lit).map(Value::Float))
+}
+
+/// Decodes a string literal (e.g. `"hello\n"`), returning the value with escapes processed.
+///
+/// Raw string literals (e.g. `r#"say "hello""#`) are also accepted and returned verbatim.
+/// Returns `None` if the literal is not a string literal.
+pub fn parse_string(lit: &Literal) -> Option<String> {
+    let repr = lit.to_string();
+    if let Some(raw) = repr.strip_prefix('r') {
+        return strip_raw(raw).map(|inner| inner.replace("\r\n", "\n"));
+    }
+    let inner = repr.strip_prefix('"')?.strip_suffix('"')?;
+    unescape(inner, false)
+}
+
+/// Decodes a byte string literal (e.g. `b"\x7Fdata"`), returning the bytes with escapes processed.
+///
+/// Raw byte string literals (e.g. `br"\d+"`) are also accepted and returned verbatim.
+/// Returns `None` if the literal is not a byte string literal.
+pub fn parse_byte_string(lit: &Literal) -> Option<Vec<u8>> {
+    let repr = lit.to_string();
+    if let Some(raw) = repr.strip_prefix("br") {
+        let inner = strip_raw(raw).filter(|inner| inner.is_ascii())?;
+        return Some(inner.replace("\r\n", "\n").into_bytes());
+    }
+    let inner = repr.strip_prefix("b\"")?.strip_suffix('"')?;
+    unescape(inner, true).map(latin1_bytes)
+}
+
+/// Decodes a character literal (e.g. `'a'` or `'\n'`), returning the char with escapes processed.
+///
+/// Returns `None` if the literal is not a character literal.
+pub fn parse_char(lit: &Literal) -> Option<char> {
+    let repr = lit.to_string();
+    let inner = repr.strip_prefix('\'')?.strip_suffix('\'')?;
+    single_char(&unescape(inner, false)?)
+}
+
+/// Decodes a byte literal (e.g. `b'a'` or `b'\xFF'`), returning the byte with escapes processed.
+///
+/// Returns `None` if the literal is not a byte literal.
+pub fn parse_byte(lit: &Literal) -> Option<u8> {
+    let repr = lit.to_string();
+    let inner = repr.strip_prefix("b'")?.strip_suffix('\'')?;
+    single_char(&unescape(inner, true)?).map(|ch| ch as u8)
+}
+
+/// Decodes an integer literal (e.g. `1_000u32`), returning the value and the type suffix (if any).
+///
+/// Hexadecimal (`0xFF`), octal (`0o755`), and binary (`0b1010`) literals are also accepted.
+/// Returns `None` if the literal is not an integer literal or the value doesn't fit in a `u128`.
+pub fn parse_int(lit: &Literal) -> Option<(u128, Option<String>)> {
+    let repr = lit.to_string();
+    let (radix, number) = match repr.get(..2) {
+        Some("0x") => (16, &repr[2..]),
+        Some("0o") => (8, &repr[2..]),
+        Some("0b") => (2, &repr[2..]),
+        _ => (10, &repr[..]),
+    };
+    let (digits, suffix) = split_suffix(number, |ch| ch.is_digit(radix) || ch == '_');
+    if radix == 10 && !digits.starts_with(|ch: char| ch.is_ascii_digit()) {
+        return None;
+    }
+    let value = u128::from_str_radix(&digits.replace('_', ""), radix).ok()?;
+    match suffix {
+        "" => Some((value, None)),
+        "i8" | "i16" | "i32" | "i64" | "i128" | "isize" |
+        "u8" | "u16" | "u32" | "u64" | "u128" | "usize" => Some((value, Some(suffix.to_string()))),
+        _ => None,
+    }
+}
+
+/// Decodes a floating-point literal (e.g. `1.5`, `2e10`, or `3f32`).
+///
+/// Returns `None` if the literal is not a float literal (integer literals are rejected).
+pub fn parse_float(lit: &Literal) -> Option<f64> {
+    let repr = lit.to_string();
+    let (number, suffixed) = match repr.strip_suffix("f32").or_else(|| repr.strip_suffix("f64")) {
+        Some(number) => (number, true),
+        None => (repr.as_str(), false),
+    };
+    if !number.starts_with(|ch: char| ch.is_ascii_digit()) {
+        return None;
+    }
+    if !number.chars().all(|ch| ch.is_ascii_digit() || "_.eE+-".contains(ch)) {
+        return None;
+    }
+    if !suffixed && !number.contains(|ch| ".eE".contains(ch)) {
+        return None;
+    }
+    number.replace('_', "").parse().ok()
+}
+
+/// Decodes a decimal number literal with an arbitrary unit suffix (e.g. `100ms` or `1.5em`),
+/// returning the value (as `Value::Int` or `Value::Float`) and the suffix.
+///
+/// The suffix is empty for plain numbers. Exponents and non-decimal integers aren't supported,
+/// since they are ambiguous with units (e.g. `2em`). Returns `None` if the literal is not a
+/// number literal or the suffix isn't alphabetic.
+pub fn parse_unit(lit: &Literal) -> Option<(Value, String)> {
+    parse_unit_repr(&lit.to_string())
+}
+
+/// Decodes the textual form of a number literal with a unit suffix (see `parse_unit`).
+fn parse_unit_repr(repr: &str) -> Option<(Value, String)> {
+    if repr.starts_with("0x") || repr.starts_with("0o") || repr.starts_with("0b") {
+        return None;
+    }
+    let (number, unit) = split_suffix(repr, |ch| ch.is_ascii_digit() || ch == '_' || ch == '.');
+    if !number.starts_with(|ch: char| ch.is_ascii_digit()) || !unit.chars().all(char::is_alphabetic) {
+        return None;
+    }
+    let number = number.replace('_', "");
+    let value = if number.contains('.') {
+        Value::Float(number.parse().ok()?)
+    } else {
+        Value::Int(number.parse().ok()?, None)
+    };
+    Some((value, unit.to_string()))
+}
+
+/// Splits `repr` after the longest prefix of chars matching `pred`.
+fn split_suffix<P: Fn(char) -> bool>(repr: &str, pred: P) -> (&str, &str) {
+    let end = repr.find(|ch| !pred(ch)).unwrap_or(repr.len());
+    repr.split_at(end)
+}
+
+/// Strips the hashes and quotes surrounding the body of a raw literal (after the `r` prefix).
+fn strip_raw(raw: &str) -> Option<&str> {
+    let hashes = raw.len() - raw.trim_start_matches('#').len();
+    let inner = raw[hashes..].strip_prefix('"')?;
+    let inner = inner.strip_suffix(&raw[..hashes])?.strip_suffix('"')?;
+    Some(inner)
+}
+
+/// Returns the only char in `value`, or `None` if it has zero or multiple chars.
+fn single_char(value: &str) -> Option<char> {
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(ch), None) => Some(ch),
+        _ => None,
+    }
+}
+
+/// Converts the result of unescaping a byte literal into bytes.
+fn latin1_bytes(value: String) -> Vec<u8> {
+    value.chars().map(|ch| ch as u8).collect()
+}
+
+/// Processes the escape sequences in the body of a quoted literal.
+///
+/// When `bytes` is true the body is treated as a byte (string) literal: only ASCII is allowed,
+/// `\x` escapes may be up to `\xFF`, and each char of the result represents a single byte.
+fn unescape(body: &str, bytes: bool) -> Option<String> {
+    let mut value = String::with_capacity(body.len());
+    let mut chars = body.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => match chars.next()? {
+                '\n' => skip_whitespace(&mut chars),
+                escape => value.push(unescape_char(escape, &mut chars, bytes)?),
+            },
+            '\r' if chars.as_str().starts_with('\n') => {}
+            ch if bytes && !ch.is_ascii() => return None,
+            ch => value.push(ch),
+        }
+    }
+    Some(value)
+}
+
+/// Decodes the char for the escape sequence starting with `escape` (i.e. after the backslash).
+fn unescape_char(escape: char, chars: &mut Chars, bytes: bool) -> Option<char> {
+    match escape {
+        'n' => Some('\n'),
+        'r' => Some('\r'),
+        't' => Some('\t'),
+        '\\' => Some('\\'),
+        '0' => Some('\0'),
+        '\'' => Some('\''),
+        '"' => Some('"'),
+        'x' => {
+            let digits = chars.as_str().get(..2).filter(|digits| digits.chars().all(|ch| ch.is_ascii_hexdigit()))?;
+            let value = u8::from_str_radix(digits, 16).ok().filter(|value| bytes || *value <= 0x7F)?;
+            chars.nth(1);
+            Some(value as char)
+        }
+        'u' if !bytes => {
+            let rest = chars.as_str().strip_prefix('{')?;
+            let end = rest.find('}')?;
+            let digits = rest[..end].replace('_', "");
+            if digits.is_empty() || digits.len() > 6 || !digits.chars().all(|ch| ch.is_ascii_hexdigit()) {
+                return None;
+            }
+            let value = u32::from_str_radix(&digits, 16).ok()?;
+            chars.nth(end + 1);
+            std::char::from_u32(value)
+        }
+        _ => None,
+    }
+}
+
+/// Skips the whitespace following a line continuation (i.e. an escaped newline).
+fn skip_whitespace(chars: &mut Chars) {
+    let rest = chars.as_str();
+    let skip = rest.len() - rest.trim_start().len();
+    *chars = rest[skip..].chars();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proc_macro2::{TokenStream, TokenTree};
+
+    fn lit(source: &str) -> Literal {
+        match source.parse::<TokenStream>().unwrap().into_iter().next() {
+            Some(TokenTree::Literal(lit)) => lit,
+            tree => panic!("expected a literal, found {:?}", tree),
+        }
+    }
+
+    #[test]
+    fn strings() {
+        let cases: &[(&str, Option<&str>)] = &[
+            (r#""hello""#, Some("hello")),
+            (r#""a\nb\t\\\"""#, Some("a\nb\t\\\"")),
+            (r#""\x41\u{1F600}\u{1_F600}""#, Some("A\u{1F600}\u{1F600}")),
+            ("\"a\\\n    b\"", Some("ab")),
+            (r##"r#"say "hi""#"##, Some(r#"say "hi""#)),
+            (r#"r"\d+""#, Some(r"\d+")),
+            ("'a'", None),
+            ("b\"a\"", None),
+            ("1", None),
+        ];
+        for &(source, expected) in cases {
+            assert_eq!(parse_string(&lit(source)).as_deref(), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn byte_strings() {
+        let cases: &[(&str, Option<&[u8]>)] = &[
+            (r#"b"data""#, Some(b"data")),
+            (r#"b"\x7F\xFF\0""#, Some(b"\x7F\xFF\0")),
+            (r#"br"\d+""#, Some(br"\d+")),
+            (r#""data""#, None),
+        ];
+        for &(source, expected) in cases {
+            assert_eq!(parse_byte_string(&lit(source)).as_deref(), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn chars_and_bytes() {
+        assert_eq!(parse_char(&lit("'a'")), Some('a'));
+        assert_eq!(parse_char(&lit(r"'\n'")), Some('\n'));
+        assert_eq!(parse_char(&lit(r"'\u{e9}'")), Some('é'));
+        assert_eq!(parse_char(&lit("b'a'")), None);
+        assert_eq!(parse_byte(&lit("b'a'")), Some(b'a'));
+        assert_eq!(parse_byte(&lit(r"b'\xFF'")), Some(0xFF));
+        assert_eq!(parse_byte(&lit("'a'")), None);
+    }
+
+    /// Malformed escapes are rejected by the lexer, so they're checked on the literal body.
+    #[test]
+    fn malformed_escapes() {
+        let cases: &[(&str, bool)] = &[
+            (r"\x+1", false),
+            (r"\x+F", true),
+            (r"\x4", false),
+            (r"\x80", false),
+            (r"\u{}", false),
+            (r"\u{+41}", false),
+            (r"\u{1234567}", false),
+            (r"\u{D800}", false),
+            (r"\u{41", false),
+            (r"\u{41}", true),
+            (r"\q", false),
+            ("\\", false),
+            ("é", true),
+        ];
+        for &(body, bytes) in cases {
+            assert_eq!(unescape(body, bytes), None, "{}", body);
+        }
+        assert_eq!(unescape(r"\x80", true), Some("\u{80}".to_string()));
+        assert_eq!(unescape("a\r\nb", false), Some("a\nb".to_string()));
+    }
+
+    #[test]
+    fn ints() {
+        type Case = (&'static str, Option<(u128, Option<&'static str>)>);
+        let cases: &[Case] = &[
+            ("0", Some((0, None))),
+            ("1_000", Some((1000, None))),
+            ("42u8", Some((42, Some("u8")))),
+            ("7_i128", Some((7, Some("i128")))),
+            ("0xFF", Some((255, None))),
+            ("0xff_u8", Some((255, Some("u8")))),
+            ("0o755", Some((0o755, None))),
+            ("0b1010", Some((10, None))),
+            ("340282366920938463463374607431768211455", Some((u128::MAX, None))),
+            ("340282366920938463463374607431768211456", None),
+            ("1.5", None),
+            ("1e3", None),
+            ("2f32", None),
+            ("\"1\"", None),
+        ];
+        for &(source, expected) in cases {
+            let expected = expected.map(|(value, suffix)| (value, suffix.map(str::to_string)));
+            assert_eq!(parse_int(&lit(source)), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn floats() {
+        let cases: &[(&str, Option<f64>)] = &[
+            ("1.5", Some(1.5)),
+            ("1_000.25", Some(1000.25)),
+            ("2e3", Some(2000.0)),
+            ("2.5E-1", Some(0.25)),
+            ("3f32", Some(3.0)),
+            ("1.5f64", Some(1.5)),
+            ("1", None),
+            ("0x1e5", None),
+            ("'a'", None),
+        ];
+        for &(source, expected) in cases {
+            assert_eq!(parse_float(&lit(source)), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn units() {
+        let cases: &[(&str, Option<(Value, &str)>)] = &[
+            ("10", Some((Value::Int(10, None), ""))),
+            ("1.5", Some((Value::Float(1.5), ""))),
+            ("0x10", None),
+            ("1e5", None),
+        ];
+        for (source, expected) in cases {
+            let expected = expected.clone().map(|(value, unit)| (value, unit.to_string()));
+            assert_eq!(parse_unit(&lit(source)), expected, "{}", source);
+        }
+
+        // The fallback lexer rejects literals with unit suffixes, so these are decoded from text
+        let suffixed: &[(&str, Option<(Value, &str)>)] = &[
+            ("10px", Some((Value::Int(10, None), "px"))),
+            ("100ms", Some((Value::Int(100, None), "ms"))),
+            ("2s", Some((Value::Int(2, None), "s"))),
+            ("1.5h", Some((Value::Float(1.5), "h"))),
+            ("1_000.5em", Some((Value::Float(1000.5), "em"))),
+            ("50%", None),
+            ("10p2", None),
+            ("px", None),
+        ];
+        for (source, expected) in suffixed {
+            let expected = expected.clone().map(|(value, unit)| (value, unit.to_string()));
+            assert_eq!(parse_unit_repr(source), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn values() {
+        assert_eq!(parse(&lit("\"s\"")), Some(Value::Str("s".to_string())));
+        assert_eq!(parse(&lit("b'b'")), Some(Value::Byte(b'b')));
+        assert_eq!(parse(&lit("'c'")), Some(Value::Char('c')));
+        assert_eq!(parse(&lit("5usize")), Some(Value::Int(5, Some("usize".to_string()))));
+        assert_eq!(parse(&lit("0.5")), Some(Value::Float(0.5)));
+    }
+}
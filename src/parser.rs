@@ -1,10 +1,21 @@
 //! A collection of parsers for `Token`s (similar to `combine::parser::{char, byte, item}`).
 
-use crate::input::Token;
-use combine::{ParseError, Parser, Stream, StreamOnce};
-use combine::error::{ConsumedResult, FastResult::*, Info, Tracked};
+use crate::input::{display_eq, join_spans, tokens_to_stream, whitespace_between, with_ident_str, GroupInput, Input, Token};
+use crate::literal;
+use crate::syntax;
+use combine::{attempt, between, look_ahead, many, optional, position, sep_end_by, sep_end_by1, ErrorOffset, ParseError, ParseResult, Parser, Stream, StreamOnce};
+use combine::easy;
+use combine::error::{ConsumedResult, FastResult::*, Info, StreamError, Tracked};
+use combine::parser::repeat::SepEndBy1;
 use combine::stream::uncons;
+use proc_macro2::{Spacing, Span, TokenTree};
+use std::convert::TryFrom;
+use std::fmt;
 use std::marker::PhantomData;
+use std::ops::RangeInclusive;
+
+pub mod pratt;
+pub mod tokens;
 
 /// Parses an ident token and returns the inner `proc_macro::Ident`.
 pub fn ident<I>() -> Ident<I>
@@ -48,36 +59,40 @@ where
     }
 }
 
-/// Parses an ident token and succeeds if the ident is equal to `word`.
-pub fn keyword<I>(word: &'static str) -> Keyword<I>
+/// Parses an ident token and succeeds if `predicate` returns true for the ident.
+///
+/// The `label` describes the accepted idents in error messages (e.g. `"snake_case name"`).
+pub fn ident_if<I, F>(label: &'static str, predicate: F) -> IdentIf<I, F>
 where
     I: Stream<Item = Token>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
+    F: FnMut(&proc_macro2::Ident) -> bool,
 {
-    Keyword(word, PhantomData)
+    IdentIf(label, predicate, PhantomData)
 }
 
 #[derive(Copy, Clone)]
-/// Represents the return type of `keyword`.
-pub struct Keyword<I>(&'static str, PhantomData<fn(I) -> I>)
+/// Represents the return type of `ident_if`.
+pub struct IdentIf<I, F>(&'static str, F, PhantomData<fn(I) -> I>)
 where
     I: Stream<Item = Token>,
     I::Error: ParseError<I::Item, I::Range, I::Position>;
 
-impl<I> Parser for Keyword<I>
+impl<I, F> Parser for IdentIf<I, F>
 where
     I: Stream<Item = Token>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
+    F: FnMut(&proc_macro2::Ident) -> bool,
 {
     type Input = I;
-    type Output = Token;
+    type Output = proc_macro2::Ident;
     type PartialState = ();
 
     fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
         let position = input.position();
         match uncons(input) {
             EmptyOk(tok) | ConsumedOk(tok) => match tok {
-                Token::Ident(ref ident) if ident.to_string() == self.0 => ConsumedOk(tok),
+                Token::Ident(ident) if (self.1)(&ident) => ConsumedOk(ident),
                 _ => EmptyErr(I::Error::empty(position).into()),
             },
             EmptyErr(err) => EmptyErr(err),
@@ -90,36 +105,55 @@ where
     }
 }
 
-/// Parses a literal token (e.g. string, number, etc) and returns the inner `proc_macro::Literal`.
-pub fn literal<I>() -> Literal<I>
+/// Parses an ident token and succeeds if the ident starts with `prefix` (e.g. `"_"` or `"r#"`
+/// for raw idents).
+pub fn ident_starting_with<I>(prefix: &'static str) -> IdentAffix<I>
 where
     I: Stream<Item = Token>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
 {
-    Literal(PhantomData)
+    IdentAffix(prefix, true, PhantomData)
+}
+
+/// Parses an ident token and succeeds if the ident ends with `suffix` (e.g. `"_t"`).
+pub fn ident_ending_with<I>(suffix: &'static str) -> IdentAffix<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    IdentAffix(suffix, false, PhantomData)
 }
 
 #[derive(Copy, Clone)]
-/// Represents the return type of `literal`.
-pub struct Literal<I>(PhantomData<fn(I) -> I>)
+/// Represents the return type of `ident_starting_with` and `ident_ending_with`.
+pub struct IdentAffix<I>(&'static str, bool, PhantomData<fn(I) -> I>)
 where
     I: Stream<Item = Token>,
     I::Error: ParseError<I::Item, I::Range, I::Position>;
 
-impl<I> Parser for Literal<I>
+impl<I> Parser for IdentAffix<I>
 where
     I: Stream<Item = Token>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
 {
     type Input = I;
-    type Output = proc_macro2::Literal;
+    type Output = proc_macro2::Ident;
     type PartialState = ();
 
     fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
         let position = input.position();
         match uncons(input) {
             EmptyOk(tok) | ConsumedOk(tok) => match tok {
-                Token::Literal(lit) => ConsumedOk(lit),
+                Token::Ident(ident) => {
+                    let matched = with_ident_str(&ident, |word| {
+                        if self.1 { word.starts_with(self.0) } else { word.ends_with(self.0) }
+                    });
+                    if matched {
+                        ConsumedOk(ident)
+                    } else {
+                        EmptyErr(I::Error::empty(position).into())
+                    }
+                }
                 _ => EmptyErr(I::Error::empty(position).into()),
             },
             EmptyErr(err) => EmptyErr(err),
@@ -128,43 +162,196 @@ where
     }
 
     fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
-        errors.error.add_expected(Info::Borrowed("LITERAL"));
+        let affix = if self.1 { "starting with" } else { "ending with" };
+        errors.error.add(StreamError::expected_message(format_args!("IDENT {} \"{}\"", affix, self.0)));
     }
 }
 
-/// Parses a punctuation token and succeeds if it's char representation is equal to `c`.
+/// Parses a hyphenated name (e.g. `font-size`) of idents joined by `-` puncts, returning the
+/// name and its span.
 ///
-/// Cannot match delimiter characters (i.e. `(`, `)`, `{`, `}`, `[, `]`).
-/// To match a delimiter use `delim` instead.
-pub fn punct<I>(c: char) -> Punct<I>
+/// The puncts must not be separated from the idents by whitespace (so `a - b` only parses `a`),
+/// which is checked using the spans of the tokens. Whitespace can only be detected with
+/// `--cfg procmacro2_semver_exempt`, so otherwise `a - b` is also parsed as `a-b`. Requires a
+/// lookahead of at least 2 tokens.
+pub fn kebab_ident<I>() -> KebabIdent<I>
 where
     I: Stream<Item = Token>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
 {
-    Punct(c, PhantomData)
+    KebabIdent(PhantomData)
 }
 
 #[derive(Copy, Clone)]
-/// Represents the return type of `punct`.
-pub struct Punct<I>(char, PhantomData<fn(I) -> I>)
+/// Represents the return type of `kebab_ident`.
+pub struct KebabIdent<I>(PhantomData<fn(I) -> I>)
 where
     I: Stream<Item = Token>,
     I::Error: ParseError<I::Item, I::Range, I::Position>;
 
-impl<I> Parser for Punct<I>
+impl<I> Parser for KebabIdent<I>
 where
     I: Stream<Item = Token>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
 {
     type Input = I;
-    type Output = Token;
+    type Output = (String, Span);
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        let first = match uncons(input) {
+            EmptyOk(Token::Ident(ident)) | ConsumedOk(Token::Ident(ident)) => ident,
+            EmptyOk(_) | ConsumedOk(_) => return EmptyErr(I::Error::empty(position).into()),
+            EmptyErr(err) => return EmptyErr(err),
+            ConsumedErr(err) => return ConsumedErr(err),
+        };
+        let mut name = first.to_string();
+        let mut last = first.span();
+        loop {
+            let checkpoint = input.checkpoint();
+            match kebab_segment(input, last) {
+                Some(next) => {
+                    name.push('-');
+                    name.push_str(&next.to_string());
+                    last = next.span();
+                }
+                None => {
+                    input.reset(checkpoint);
+                    break;
+                }
+            }
+        }
+        ConsumedOk((name, join_spans(first.span(), last)))
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        errors.error.add_expected(Info::Borrowed("IDENT"));
+    }
+}
+
+/// Parses a `-` and an ident directly following the token spanning `last`.
+fn kebab_segment<I>(input: &mut I, last: Span) -> Option<proc_macro2::Ident>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    let dash = match input.uncons() {
+        Ok(Token::Punct(ref punct)) if punct.as_char() == '-' && punct.spacing() == Spacing::Alone => punct.span(),
+        _ => return None,
+    };
+    match input.uncons() {
+        Ok(Token::Ident(ident)) if !whitespace_between(last, dash) && !whitespace_between(dash, ident.span()) => Some(ident),
+        _ => None,
+    }
+}
+
+/// The strict and reserved keywords of Rust (sorted, for binary search).
+pub const RUST_KEYWORDS: &[&str] = &[
+    "Self", "abstract", "as", "async", "await", "become", "box", "break", "const", "continue",
+    "crate", "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if",
+    "impl", "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv",
+    "pub", "ref", "return", "self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Parses an ident token and succeeds if the ident is not a Rust keyword (see `RUST_KEYWORDS`).
+///
+/// Useful when idents from the input are re-emitted as names in the generated code.
+pub fn non_keyword_ident<I>() -> IdentIf<I, fn(&proc_macro2::Ident) -> bool>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    ident_if("non-keyword IDENT", |ident| with_ident_str(ident, |word| RUST_KEYWORDS.binary_search(&word).is_err()))
+}
+
+/// A set of reserved words for a grammar, stored as a sorted table for binary search.
+///
+/// Declare the set once as a constant and use it with `reserved` and `unreserved_ident`:
+///
+/// ```rust
+/// use combine_proc_macro::parser::KeywordSet;
+///
+/// const SQL_KEYWORDS: KeywordSet = KeywordSet::new("SQL keyword", &["from", "select", "where"]);
+/// assert!(SQL_KEYWORDS.contains("select"));
+/// assert!(!SQL_KEYWORDS.contains("users"));
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct KeywordSet {
+    label: &'static str,
+    words: &'static [&'static str],
+}
+
+impl KeywordSet {
+    /// The strict and reserved keywords of Rust (see `RUST_KEYWORDS`).
+    pub const RUST: KeywordSet = KeywordSet::new("keyword", RUST_KEYWORDS);
+
+    /// Creates a keyword set from `words`, which must be sorted and contain no duplicates.
+    ///
+    /// The `label` describes the set in error messages (e.g. `"SQL keyword"`).
+    pub const fn new(label: &'static str, words: &'static [&'static str]) -> KeywordSet {
+        KeywordSet { label, words }
+    }
+
+    /// Returns the label describing the set in error messages.
+    pub fn label(&self) -> &'static str {
+        self.label
+    }
+
+    /// Returns the words in the set (in sorted order).
+    pub fn words(&self) -> &'static [&'static str] {
+        self.words
+    }
+
+    /// Returns the word in the set equal to `word`, if any.
+    pub fn get(&self, word: &str) -> Option<&'static str> {
+        debug_assert!(self.words.windows(2).all(|pair| pair[0] < pair[1]), "KeywordSet words must be sorted");
+        self.words.binary_search(&word).ok().map(|index| self.words[index])
+    }
+
+    /// Returns true if `word` is in the set.
+    pub fn contains(&self, word: &str) -> bool {
+        self.get(word).is_some()
+    }
+}
+
+/// Parses an ident token and succeeds if the ident is one of the words in `set`.
+///
+/// Returns the matched word from the set along with the ident. On failure the set's label is
+/// reported as expected (rather than every word in the set).
+pub fn reserved<I>(set: KeywordSet) -> Reserved<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    Reserved(set, PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `reserved`.
+pub struct Reserved<I>(KeywordSet, PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for Reserved<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = (&'static str, proc_macro2::Ident);
     type PartialState = ();
 
     fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
         let position = input.position();
         match uncons(input) {
             EmptyOk(tok) | ConsumedOk(tok) => match tok {
-                Token::Punct(ref punct) if punct.as_char() == self.0 => ConsumedOk(tok),
+                Token::Ident(ident) => match with_ident_str(&ident, |word| self.0.get(word)) {
+                    Some(word) => ConsumedOk((word, ident)),
+                    None => EmptyErr(I::Error::empty(position).into()),
+                },
                 _ => EmptyErr(I::Error::empty(position).into()),
             },
             EmptyErr(err) => EmptyErr(err),
@@ -173,28 +360,69 @@ where
     }
 
     fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
-        errors.error.add_expected(Info::Token(Token::Punct(proc_macro2::Punct::new(self.0, proc_macro2::Spacing::Alone))));
+        errors.error.add_expected(Info::Borrowed(self.0.label));
     }
 }
 
+/// Parses an ident token and succeeds if the ident is not one of the words in `set`.
+pub fn unreserved_ident<I>(set: KeywordSet) -> UnreservedIdent<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    UnreservedIdent(set, PhantomData)
+}
 
-/// Parses a delimiter if it's char representation is equal to `c`.
-pub fn delim<I>(c: char) -> Delim<I>
+#[derive(Copy, Clone)]
+/// Represents the return type of `unreserved_ident`.
+pub struct UnreservedIdent<I>(KeywordSet, PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for UnreservedIdent<I>
 where
     I: Stream<Item = Token>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
 {
-    Delim(c, PhantomData)
+    type Input = I;
+    type Output = proc_macro2::Ident;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        match uncons(input) {
+            EmptyOk(tok) | ConsumedOk(tok) => match tok {
+                Token::Ident(ident) if !with_ident_str(&ident, |word| self.0.contains(word)) => ConsumedOk(ident),
+                _ => EmptyErr(I::Error::empty(position).into()),
+            },
+            EmptyErr(err) => EmptyErr(err),
+            ConsumedErr(err) => ConsumedErr(err),
+        }
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        errors.error.add_expected(Info::Borrowed("IDENT"));
+    }
+}
+
+/// Parses an ident token and succeeds if the ident is equal to `word`.
+pub fn keyword<I>(word: &'static str) -> Keyword<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    Keyword(word, PhantomData)
 }
 
 #[derive(Copy, Clone)]
-/// Represents the return type of `delim`.
-pub struct Delim<I>(char, PhantomData<fn(I) -> I>)
+/// Represents the return type of `keyword`.
+pub struct Keyword<I>(&'static str, PhantomData<fn(I) -> I>)
 where
     I: Stream<Item = Token>,
     I::Error: ParseError<I::Item, I::Range, I::Position>;
 
-impl<I> Parser for Delim<I>
+impl<I> Parser for Keyword<I>
 where
     I: Stream<Item = Token>,
     I::Error: ParseError<I::Item, I::Range, I::Position>,
@@ -207,7 +435,7 @@ where
         let position = input.position();
         match uncons(input) {
             EmptyOk(tok) | ConsumedOk(tok) => match tok {
-                Token::Delim(ch, _) if ch == self.0 => ConsumedOk(tok),
+                Token::Ident(ref ident) if with_ident_str(ident, |word| word == self.0) => ConsumedOk(tok),
                 _ => EmptyErr(I::Error::empty(position).into()),
             },
             EmptyErr(err) => EmptyErr(err),
@@ -216,6 +444,3091 @@ where
     }
 
     fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
-        errors.error.add_expected(Info::Token(Token::Delim(self.0, proc_macro2::Span::call_site())));
+        errors.error.add_expected(Info::Borrowed(self.0));
+    }
+}
+
+/// Parses an ident token equal to `word` like `keyword`, but returns the inner `proc_macro::Ident`.
+pub fn keyword_ident<I>(word: &'static str) -> KeywordIdent<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    KeywordIdent(word, false, PhantomData)
+}
+
+/// Parses an ident token equal to `word` ignoring ASCII case (e.g. `SELECT` or `Select` for
+/// `"select"`), returning the inner `proc_macro::Ident`.
+pub fn keyword_ident_ignore_case<I>(word: &'static str) -> KeywordIdent<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    KeywordIdent(word, true, PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `keyword_ident` and `keyword_ident_ignore_case`.
+pub struct KeywordIdent<I>(&'static str, bool, PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for KeywordIdent<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = proc_macro2::Ident;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        match uncons(input) {
+            EmptyOk(tok) | ConsumedOk(tok) => match tok {
+                Token::Ident(ident) if with_ident_str(&ident, |word| word == self.0 || (self.1 && word.eq_ignore_ascii_case(self.0))) => {
+                    ConsumedOk(ident)
+                }
+                _ => EmptyErr(I::Error::empty(position).into()),
+            },
+            EmptyErr(err) => EmptyErr(err),
+            ConsumedErr(err) => ConsumedErr(err),
+        }
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        errors.error.add_expected(Info::Borrowed(self.0));
+    }
+}
+
+/// Parses an optional ident equal to `word` (e.g. `mut`) and returns whether it was present.
+///
+/// Never fails; when the keyword is absent no input is consumed.
+pub fn optional_keyword<I>(word: &'static str) -> impl Parser<Input = I, Output = bool>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    optional(keyword_ident(word)).map(|ident| ident.is_some())
+}
+
+/// Parses an optional ident equal to `word` like `optional_keyword`, but returns the keyword's
+/// span when it's present.
+pub fn optional_keyword_span<I>(word: &'static str) -> impl Parser<Input = I, Output = Option<Span>>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    optional(keyword_ident(word)).map(|ident| ident.map(|ident| ident.span()))
+}
+
+/// Parses the contextual keyword `word` when it's followed by input matching `followed_by`.
+///
+/// Returns the keyword's ident; `followed_by` is only looked ahead at, not consumed. If either
+/// doesn't match no input is consumed, so the same word can still be parsed as a plain ident
+/// by a later alternative (e.g. `soft_keyword("union", ident())` for `union Foo { .. }` but
+/// `ident()` for `union = 1`). Soft keywords should not be part of a `KeywordSet` used with
+/// `unreserved_ident`. The input must support lookahead for the keyword plus `followed_by`.
+pub fn soft_keyword<I, P>(word: &'static str, followed_by: P) -> impl Parser<Input = I, Output = proc_macro2::Ident>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    P: Parser<Input = I>,
+{
+    attempt(keyword_ident(word).skip(look_ahead(followed_by)))
+}
+
+/// Parses a sequence of idents equal to `words` as one multi-word keyword (e.g. `group by`)
+/// and returns a span covering all of the words.
+///
+/// Fails without consuming input unless every word matches, with a single expected label for
+/// the whole keyword (e.g. "Expected `group by`"). This requires the input to support at
+/// least `words.len()` tokens of lookahead (see `Input::with_lookahead`).
+pub fn keywords<I>(words: &'static [&'static str]) -> Keywords<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    Keywords(words, PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `keywords`.
+pub struct Keywords<I>(&'static [&'static str], PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for Keywords<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = Span;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        let checkpoint = input.checkpoint();
+        let mut spans = Vec::with_capacity(self.0.len());
+        for word in self.0 {
+            match input.uncons() {
+                Ok(Token::Ident(ref ident)) if with_ident_str(ident, |text| text == *word) => spans.push(ident.span()),
+                _ => {
+                    input.reset(checkpoint);
+                    return EmptyErr(I::Error::empty(position).into());
+                }
+            }
+        }
+        match (spans.first(), spans.last()) {
+            (Some(first), Some(last)) => ConsumedOk(join_spans(*first, *last)),
+            _ => EmptyOk(Span::call_site()),
+        }
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        errors.error.add(StreamError::expected_message(self.0.join(" ")));
+    }
+}
+
+/// Parses an ident token and succeeds if the ident is equal to one of `words`.
+///
+/// Returns the index of the matched word in `words` along with the ident.
+pub fn keyword_any<I>(words: &'static [&'static str]) -> KeywordAny<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    KeywordAny(words, PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `keyword_any`.
+pub struct KeywordAny<I>(&'static [&'static str], PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for KeywordAny<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = (usize, proc_macro2::Ident);
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        match uncons(input) {
+            EmptyOk(tok) | ConsumedOk(tok) => match tok {
+                Token::Ident(ident) => {
+                    match with_ident_str(&ident, |word| self.0.iter().position(|kw| *kw == word)) {
+                        Some(index) => ConsumedOk((index, ident)),
+                        None => EmptyErr(I::Error::empty(position).into()),
+                    }
+                }
+                _ => EmptyErr(I::Error::empty(position).into()),
+            },
+            EmptyErr(err) => EmptyErr(err),
+            ConsumedErr(err) => ConsumedErr(err),
+        }
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        for word in self.0 {
+            errors.error.add_expected(Info::Borrowed(word));
+        }
+    }
+}
+
+/// Parses any of the keywords in `words` in any order, each at most once (e.g. modifiers like
+/// `unique sparse nullable`), and returns which ones were present.
+///
+/// Stops before the first ident that isn't one of `words`. A repeated keyword is an error at
+/// the repeated keyword's position (see `SpanPosition`).
+pub fn flag_set<I>(words: &'static [&'static str]) -> FlagSet<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    FlagSet(words, PhantomData)
+}
+
+/// The keywords found by `flag_set`.
+#[derive(Clone, Debug)]
+pub struct Flags {
+    words: &'static [&'static str],
+    spans: Vec<Option<Span>>,
+}
+
+impl Flags {
+    /// Returns true if `word` was present.
+    pub fn contains(&self, word: &str) -> bool {
+        self.span(word).is_some()
+    }
+
+    /// Returns the span of `word` if it was present.
+    pub fn span(&self, word: &str) -> Option<Span> {
+        let index = self.words.iter().position(|flag| *flag == word)?;
+        self.spans[index]
+    }
+
+    /// Returns a bitset of the present keywords, where bit `i` is set if `words[i]` was present.
+    ///
+    /// Only the first 64 keywords are represented.
+    pub fn bits(&self) -> u64 {
+        self.spans.iter().take(64).enumerate()
+            .filter(|(_, span)| span.is_some())
+            .fold(0, |bits, (index, _)| bits | 1 << index)
+    }
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `flag_set`.
+pub struct FlagSet<I>(&'static [&'static str], PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for FlagSet<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = Flags;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let mut flags = Flags { words: self.0, spans: vec![None; self.0.len()] };
+        let mut consumed = false;
+        loop {
+            let checkpoint = input.checkpoint();
+            let found = match input.uncons() {
+                Ok(Token::Ident(ref ident)) => {
+                    with_ident_str(ident, |text| self.0.iter().position(|word| text == *word)).map(|index| (index, ident.span()))
+                }
+                _ => None,
+            };
+            let (index, span) = match found {
+                Some(found) => found,
+                None => {
+                    input.reset(checkpoint);
+                    break;
+                }
+            };
+            if flags.spans[index].is_some() {
+                let mut err = I::Error::from_error(input.position(), StreamError::message_static_message("duplicate keyword"));
+                err.add_unexpected(Info::Borrowed(self.0[index]));
+                return ConsumedErr(err);
+            }
+            flags.spans[index] = Some(span);
+            consumed = true;
+        }
+        if consumed { ConsumedOk(flags) } else { EmptyOk(flags) }
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        for word in self.0 {
+            errors.error.add_expected(Info::Borrowed(word));
+        }
+    }
+}
+
+/// Parses a literal token (e.g. string, number, etc) and returns the inner `proc_macro::Literal`.
+pub fn literal<I>() -> Literal<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    Literal(PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `literal`.
+pub struct Literal<I>(PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for Literal<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = proc_macro2::Literal;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        match uncons(input) {
+            EmptyOk(tok) | ConsumedOk(tok) => match tok {
+                Token::Literal(lit) => ConsumedOk(lit),
+                _ => EmptyErr(I::Error::empty(position).into()),
+            },
+            EmptyErr(err) => EmptyErr(err),
+            ConsumedErr(err) => ConsumedErr(err),
+        }
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        errors.error.add_expected(Info::Borrowed("LITERAL"));
+    }
+}
+
+/// Parses a literal token and succeeds if its textual form is equal to `text` (e.g. `"\"GET\""` or `"0u8"`).
+pub fn literal_matching<I>(text: &'static str) -> LiteralMatching<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    LiteralMatching(text, PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `literal_matching`.
+pub struct LiteralMatching<I>(&'static str, PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for LiteralMatching<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = proc_macro2::Literal;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        match uncons(input) {
+            EmptyOk(tok) | ConsumedOk(tok) => match tok {
+                Token::Literal(lit) if display_eq(&lit, self.0) => ConsumedOk(lit),
+                _ => EmptyErr(I::Error::empty(position).into()),
+            },
+            EmptyErr(err) => EmptyErr(err),
+            ConsumedErr(err) => ConsumedErr(err),
+        }
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        errors.error.add_expected(Info::Borrowed(self.0));
+    }
+}
+
+/// Parses a string literal and returns its value (with escapes processed) and span.
+pub fn string_lit<I>() -> LiteralAs<I, String>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    LiteralAs(literal::parse_string, "STRING", PhantomData)
+}
+
+/// Parses an integer literal and returns its value, type suffix (e.g. `"u32"`), and span.
+///
+/// Decimal, hexadecimal (`0xFF`), octal (`0o755`), and binary (`0b1010`) literals are accepted.
+pub fn int_lit<I>() -> LiteralAs<I, (u128, Option<String>)>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    LiteralAs(literal::parse_int, "INTEGER", PhantomData)
+}
+
+/// Parses a floating-point literal and returns its value and span.
+///
+/// Integer literals are not accepted, e.g. `1` fails but `1.0` or `1f64` succeed.
+pub fn float_lit<I>() -> LiteralAs<I, f64>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    LiteralAs(literal::parse_float, "FLOAT", PhantomData)
+}
+
+/// Parses a character literal and returns its value (with escapes processed) and span.
+pub fn char_lit<I>() -> LiteralAs<I, char>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    LiteralAs(literal::parse_char, "CHAR", PhantomData)
+}
+
+/// Parses a byte string literal and returns its bytes (with escapes processed) and span.
+pub fn byte_string_lit<I>() -> LiteralAs<I, Vec<u8>>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    LiteralAs(literal::parse_byte_string, "BYTE_STRING", PhantomData)
+}
+
+/// Parses a byte literal and returns its value (with escapes processed) and span.
+pub fn byte_lit<I>() -> LiteralAs<I, u8>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    LiteralAs(literal::parse_byte, "BYTE", PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of the typed literal parsers (e.g. `string_lit`).
+pub struct LiteralAs<I, T>(fn(&proc_macro2::Literal) -> Option<T>, &'static str, PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I, T> Parser for LiteralAs<I, T>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = (T, Span);
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        match uncons(input) {
+            EmptyOk(tok) | ConsumedOk(tok) => match tok {
+                Token::Literal(ref lit) => match (self.0)(lit) {
+                    Some(value) => ConsumedOk((value, lit.span())),
+                    None => EmptyErr(I::Error::empty(position).into()),
+                },
+                _ => EmptyErr(I::Error::empty(position).into()),
+            },
+            EmptyErr(err) => EmptyErr(err),
+            ConsumedErr(err) => ConsumedErr(err),
+        }
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        errors.error.add_expected(Info::Borrowed(self.1));
+    }
+}
+
+/// Parses an integer literal with an optional leading `-` and returns its value, suffix, and span.
+///
+/// Fails without consuming input if the value doesn't fit in an `i128`, or if a negative
+/// literal has an unsigned suffix (e.g. `-1u8`).
+pub fn signed_int_lit<I>() -> SignedLiteralAs<I, (i128, Option<String>)>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    SignedLiteralAs(|lit, negative| {
+        let (value, suffix) = literal::parse_int(lit)?;
+        let value = if negative {
+            if matches!(suffix, Some(ref suffix) if suffix.starts_with('u')) || value > i128::MIN.unsigned_abs() {
+                return None;
+            }
+            (value as i128).wrapping_neg()
+        } else {
+            i128::try_from(value).ok()?
+        };
+        Some((value, suffix))
+    }, "INTEGER", PhantomData)
+}
+
+/// Parses a floating-point literal with an optional leading `-` and returns its value and span.
+pub fn signed_float_lit<I>() -> SignedLiteralAs<I, f64>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    SignedLiteralAs(|lit, negative| {
+        let value = literal::parse_float(lit)?;
+        Some(if negative { -value } else { value })
+    }, "FLOAT", PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of the signed literal parsers (e.g. `signed_int_lit`).
+pub struct SignedLiteralAs<I, T>(fn(&proc_macro2::Literal, bool) -> Option<T>, &'static str, PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I, T> Parser for SignedLiteralAs<I, T>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = (T, Span);
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        let checkpoint = input.checkpoint();
+        let mut minus = None;
+        while let Ok(tok) = input.uncons() {
+            match tok {
+                Token::Punct(ref punct) if minus.is_none() && punct.as_char() == '-' => {
+                    minus = Some(punct.span());
+                }
+                Token::Literal(ref lit) => {
+                    if let Some(value) = (self.0)(lit, minus.is_some()) {
+                        let span = minus.map_or(lit.span(), |minus| join_spans(minus, lit.span()));
+                        return ConsumedOk((value, span));
+                    }
+                    break;
+                }
+                _ => break,
+            }
+        }
+        input.reset(checkpoint);
+        EmptyErr(I::Error::empty(position).into())
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        errors.error.add_expected(Info::Borrowed(self.1));
+    }
+}
+
+/// Parses an integer literal and converts it to `T` (e.g. `int_lit_as::<u8, _>()`).
+///
+/// Returns the converted value and the literal's span. If the value doesn't fit in `T` the
+/// parser fails after consuming the literal, with the error at the position of the literal
+/// (including a leading `-`) rather than the token after it.
+pub fn int_lit_as<T, I>() -> IntLitAs<LiteralAs<I, (u128, Option<String>)>, T>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    T: TryFrom<u128>,
+{
+    IntLitAs(int_lit(), PhantomData)
+}
+
+/// Parses an integer literal with an optional leading `-` (see `signed_int_lit`) and converts
+/// it to `T` (e.g. `signed_int_lit_as::<i8, _>()`), failing like `int_lit_as` on overflow.
+pub fn signed_int_lit_as<T, I>() -> IntLitAs<SignedLiteralAs<I, (i128, Option<String>)>, T>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    T: TryFrom<i128>,
+{
+    IntLitAs(signed_int_lit(), PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `int_lit_as` and `signed_int_lit_as`.
+pub struct IntLitAs<P, T>(P, PhantomData<fn() -> T>);
+
+impl<I, P, V, T> Parser for IntLitAs<P, T>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    P: Parser<Input = I, Output = ((V, Option<String>), Span)>,
+    T: TryFrom<V>,
+{
+    type Input = I;
+    type Output = (T, Span);
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        let ((value, _), span) = match self.0.parse_lazy(input) {
+            EmptyOk(output) | ConsumedOk(output) => output,
+            EmptyErr(err) => return EmptyErr(err),
+            ConsumedErr(err) => return ConsumedErr(err),
+        };
+        match T::try_from(value) {
+            Ok(value) => ConsumedOk((value, span)),
+            Err(_) => {
+                let message = StreamError::message_static_message("integer literal out of range");
+                let mut err = I::Error::from_error(position, message);
+                err.add_expected(Info::Borrowed(std::any::type_name::<T>()));
+                ConsumedErr(err)
+            }
+        }
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        self.0.add_error(errors);
+    }
+}
+
+/// Parses an integer literal with an optional leading `-` and checks that it's within `range`
+/// (e.g. `int_in_range(0u8..=100)`).
+///
+/// Returns the value and span. If the value is out of range the parser fails after consuming
+/// the literal, with an error like "expected integer in range 0..=100" at the position of the
+/// literal.
+pub fn int_in_range<I, T>(range: RangeInclusive<T>) -> IntInRange<I, T>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    T: Copy + fmt::Display + Into<i128> + TryFrom<i128>,
+{
+    IntInRange(signed_int_lit(), range)
+}
+
+#[derive(Clone)]
+/// Represents the return type of `int_in_range`.
+pub struct IntInRange<I, T>(SignedLiteralAs<I, (i128, Option<String>)>, RangeInclusive<T>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I, T> Parser for IntInRange<I, T>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    T: Copy + fmt::Display + Into<i128> + TryFrom<i128>,
+{
+    type Input = I;
+    type Output = (T, Span);
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        let ((value, _), span) = match self.0.parse_lazy(input) {
+            EmptyOk(output) | ConsumedOk(output) => output,
+            EmptyErr(err) => return EmptyErr(err),
+            ConsumedErr(err) => return ConsumedErr(err),
+        };
+        let (start, end) = (*self.1.start(), *self.1.end());
+        if (start.into()..=end.into()).contains(&value) {
+            if let Ok(value) = T::try_from(value) {
+                return ConsumedOk((value, span));
+            }
+        }
+        let range = format!("integer in range {}..={}", start, end);
+        ConsumedErr(I::Error::from_error(position, StreamError::expected_message(range)))
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        self.0.add_error(errors);
+    }
+}
+
+/// Parses a name given either as an ident (e.g. `users`) or a string literal (e.g. `"user table"`).
+///
+/// Returns the name (with escapes processed for strings), its span, and the original token so
+/// it can be re-emitted as written.
+pub fn string_or_ident<I>() -> StringOrIdent<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    StringOrIdent(PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `string_or_ident`.
+pub struct StringOrIdent<I>(PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for StringOrIdent<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = (String, Span, Token);
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        match uncons(input) {
+            EmptyOk(tok) | ConsumedOk(tok) => {
+                let name = match tok {
+                    Token::Ident(ref ident) => Some(ident.to_string()),
+                    Token::Literal(ref lit) => literal::parse_string(lit),
+                    _ => None,
+                };
+                match name {
+                    Some(name) => ConsumedOk((name, tok.span(), tok)),
+                    None => EmptyErr(I::Error::empty(position).into()),
+                }
+            }
+            EmptyErr(err) => EmptyErr(err),
+            ConsumedErr(err) => ConsumedErr(err),
+        }
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        errors.error.add_expected(Info::Borrowed("IDENT"));
+        errors.error.add_expected(Info::Borrowed("STRING"));
+    }
+}
+
+/// Parses the keyword `true` or `false` and returns its value and span.
+pub fn bool_lit<I>() -> BoolLit<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    BoolLit(PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `bool_lit`.
+pub struct BoolLit<I>(PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for BoolLit<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = (bool, Span);
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        match uncons(input) {
+            EmptyOk(tok) | ConsumedOk(tok) => match tok {
+                Token::Ident(ref ident) if with_ident_str(ident, |word| word == "true") => ConsumedOk((true, ident.span())),
+                Token::Ident(ref ident) if with_ident_str(ident, |word| word == "false") => ConsumedOk((false, ident.span())),
+                _ => EmptyErr(I::Error::empty(position).into()),
+            },
+            EmptyErr(err) => EmptyErr(err),
+            ConsumedErr(err) => ConsumedErr(err),
+        }
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        errors.error.add_expected(Info::Borrowed("true"));
+        errors.error.add_expected(Info::Borrowed("false"));
+    }
+}
+
+/// The time units accepted by `duration_lit` and their length in nanoseconds.
+const TIME_UNITS: &[(&str, u128)] = &[
+    ("ns", 1),
+    ("us", 1_000),
+    ("ms", 1_000_000),
+    ("s", 1_000_000_000),
+    ("m", 60_000_000_000),
+    ("min", 60_000_000_000),
+    ("h", 3_600_000_000_000),
+    ("d", 86_400_000_000_000),
+];
+
+/// Parses a number with a time unit (e.g. `5s`, `100ms`, `1.5h`) and returns the duration and
+/// its span.
+///
+/// The unit is either the literal's suffix (`100ms` is a single literal) or an ident directly
+/// following an unsuffixed literal. Whitespace before the ident can only be detected with
+/// `--cfg procmacro2_semver_exempt`, so otherwise `5 s` is also accepted. The units are `ns`,
+/// `us`, `ms`, `s`, `m` or `min`, `h`, and `d`. A number without a unit or with an unknown unit
+/// fails after consuming the number. Requires a lookahead of at least 2 tokens.
+pub fn duration_lit<I>() -> DurationLit<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    DurationLit(PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `duration_lit`.
+pub struct DurationLit<I>(PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for DurationLit<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = (std::time::Duration, Span);
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        let (value, unit, span) = match parse_unit_suffix(input, false) {
+            Ok(Some(parsed)) => parsed,
+            Ok(None) => return EmptyErr(I::Error::empty(position).into()),
+            Err(err) => return EmptyErr(err.into()),
+        };
+        let nanos = TIME_UNITS.iter().find(|(name, _)| *name == unit).map(|(_, nanos)| *nanos);
+        let duration = nanos.and_then(|nanos| match value {
+            literal::Value::Int(value, _) => value.checked_mul(nanos),
+            literal::Value::Float(value) => Some((value * nanos as f64).round() as u128),
+            _ => None,
+        });
+        let duration = duration.and_then(|nanos| {
+            let secs = u64::try_from(nanos / 1_000_000_000).ok()?;
+            Some(std::time::Duration::new(secs, (nanos % 1_000_000_000) as u32))
+        });
+        match duration {
+            Some(duration) => ConsumedOk((duration, span)),
+            None if nanos.is_none() => {
+                let message = StreamError::message_static_message("expected a time unit");
+                let mut err = I::Error::from_error(input.position(), message);
+                err.add_expected(Info::Borrowed("ns, us, ms, s, m, min, h, or d"));
+                ConsumedErr(err)
+            }
+            None => {
+                let message = StreamError::message_static_message("duration out of range");
+                ConsumedErr(I::Error::from_error(input.position(), message))
+            }
+        }
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        errors.error.add_expected(Info::Borrowed("DURATION"));
+    }
+}
+
+/// Parses a number literal followed by its unit (see `literal::parse_unit`), where an unsuffixed
+/// literal may instead be followed by an adjacent ident (or a `%` if `percent` is true) as its unit.
+///
+/// Returns `Ok(None)` without consuming input if the next token isn't a number literal.
+fn parse_unit_suffix<I>(input: &mut I, percent: bool) -> Result<Option<(literal::Value, String, Span)>, I::Error>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    let checkpoint = input.checkpoint();
+    let lit = match input.uncons() {
+        Ok(Token::Literal(lit)) => lit,
+        Ok(_) => {
+            input.reset(checkpoint);
+            return Ok(None);
+        }
+        Err(err) => {
+            input.reset(checkpoint);
+            return Err(I::Error::from_error(input.position(), err));
+        }
+    };
+    let (value, unit) = match literal::parse_unit(&lit) {
+        Some(parsed) => parsed,
+        None => {
+            input.reset(checkpoint);
+            return Ok(None);
+        }
+    };
+    if !unit.is_empty() {
+        return Ok(Some((value, unit, lit.span())));
+    }
+    let checkpoint = input.checkpoint();
+    match input.uncons() {
+        Ok(Token::Ident(ref ident)) if !whitespace_between(lit.span(), ident.span()) => {
+            Ok(Some((value, ident.to_string(), join_spans(lit.span(), ident.span()))))
+        }
+        Ok(Token::Punct(ref punct)) if percent && punct.as_char() == '%' && !whitespace_between(lit.span(), punct.span()) => {
+            Ok(Some((value, "%".to_string(), join_spans(lit.span(), punct.span()))))
+        }
+        _ => {
+            input.reset(checkpoint);
+            Ok(Some((value, unit, lit.span())))
+        }
+    }
+}
+
+/// Parses a number with an optional unit (e.g. `10px`, `1.5em`, or `50%`) and returns the value,
+/// the unit (empty for plain numbers), and the joined span.
+///
+/// The unit is either the literal's suffix (`10px` is a single literal) or an ident or `%`
+/// directly following an unsuffixed literal (`50%` is lexed as a literal and a `%`). Whitespace
+/// before the unit can only be detected with `--cfg procmacro2_semver_exempt`, so otherwise
+/// `50 %` is also accepted. Requires a lookahead of at least 2 tokens.
+pub fn unit_lit<I>() -> UnitLit<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    UnitLit(PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `unit_lit`.
+pub struct UnitLit<I>(PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for UnitLit<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = (f64, String, Span);
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        match parse_unit_suffix(input, true) {
+            Ok(Some((literal::Value::Int(value, _), unit, span))) => ConsumedOk((value as f64, unit, span)),
+            Ok(Some((literal::Value::Float(value), unit, span))) => ConsumedOk((value, unit, span)),
+            Ok(_) => EmptyErr(I::Error::empty(position).into()),
+            Err(err) => EmptyErr(err.into()),
+        }
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        errors.error.add_expected(Info::Borrowed("NUMBER"));
+    }
+}
+
+/// Parses a version number like `1.2.3`, returning the major, minor, and patch numbers and the
+/// joined span.
+///
+/// The version is lexed as a float literal (`1.2`), a `.` punct, and an integer literal (`3`),
+/// which must not be separated by whitespace. Whitespace can only be detected with
+/// `--cfg procmacro2_semver_exempt`, so otherwise `1.2 . 3` is also accepted.
+/// Fails without consuming input if the tokens don't form a version. Requires a lookahead of
+/// at least 3 tokens.
+pub fn version_lit<I>() -> VersionLit<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    VersionLit(PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `version_lit`.
+pub struct VersionLit<I>(PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for VersionLit<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = (u64, u64, u64, Span);
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        let checkpoint = input.checkpoint();
+        match parse_version(input) {
+            Some(version) => ConsumedOk(version),
+            None => {
+                input.reset(checkpoint);
+                EmptyErr(I::Error::empty(position).into())
+            }
+        }
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        errors.error.add_expected(Info::Borrowed("VERSION"));
+    }
+}
+
+/// Parses the tokens of a version number (see `version_lit`), or `None` if they don't match.
+fn parse_version<I>(input: &mut I) -> Option<(u64, u64, u64, Span)>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    let digits = |repr: &str| -> Option<u64> {
+        if repr.is_empty() || !repr.chars().all(|ch| ch.is_ascii_digit()) {
+            return None;
+        }
+        repr.parse().ok()
+    };
+    let major_minor = match input.uncons() {
+        Ok(Token::Literal(lit)) => lit,
+        _ => return None,
+    };
+    let repr = major_minor.to_string();
+    let mut parts = repr.splitn(2, '.');
+    let major = digits(parts.next()?)?;
+    let minor = digits(parts.next()?)?;
+    let dot = match input.uncons() {
+        Ok(Token::Punct(ref punct)) if punct.as_char() == '.' && punct.spacing() == Spacing::Alone => punct.span(),
+        _ => return None,
+    };
+    let patch_lit = match input.uncons() {
+        Ok(Token::Literal(lit)) => lit,
+        _ => return None,
+    };
+    let patch = digits(&patch_lit.to_string())?;
+    if whitespace_between(major_minor.span(), dot) || whitespace_between(dot, patch_lit.span()) {
+        return None;
+    }
+    Some((major, minor, patch, join_spans(major_minor.span(), patch_lit.span())))
+}
+
+/// Parses a punctuation token and succeeds if it's char representation is equal to `c`.
+///
+/// Cannot match delimiter characters (i.e. `(`, `)`, `{`, `}`, `[, `]`).
+/// To match a delimiter use `delim` instead.
+pub fn punct<I>(c: char) -> Punct<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    Punct(c, PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `punct`.
+pub struct Punct<I>(char, PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for Punct<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = Token;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        match uncons(input) {
+            EmptyOk(tok) | ConsumedOk(tok) => match tok {
+                Token::Punct(ref punct) if punct.as_char() == self.0 => ConsumedOk(tok),
+                _ => EmptyErr(I::Error::empty(position).into()),
+            },
+            EmptyErr(err) => EmptyErr(err),
+            ConsumedErr(err) => ConsumedErr(err),
+        }
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        errors.error.add_expected(Info::Token(Token::Punct(proc_macro2::Punct::new(self.0, proc_macro2::Spacing::Alone))));
+    }
+}
+
+/// Parses a punctuation token equal to `c` like `punct`, but returns the inner `proc_macro::Punct`.
+pub fn punct_tok<I>(c: char) -> PunctTok<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    PunctTok(c, PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `punct_tok`.
+pub struct PunctTok<I>(char, PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for PunctTok<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = proc_macro2::Punct;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        match uncons(input) {
+            EmptyOk(tok) | ConsumedOk(tok) => match tok {
+                Token::Punct(punct) if punct.as_char() == self.0 => ConsumedOk(punct),
+                _ => EmptyErr(I::Error::empty(position).into()),
+            },
+            EmptyErr(err) => EmptyErr(err),
+            ConsumedErr(err) => ConsumedErr(err),
+        }
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        errors.error.add_expected(Info::Token(Token::Punct(proc_macro2::Punct::new(self.0, proc_macro2::Spacing::Alone))));
+    }
+}
+
+/// Parses a punctuation token equal to `c` that is joined to the following token (e.g. the `<` in `<-`).
+pub fn punct_joint<I>(c: char) -> PunctSpacing<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    PunctSpacing(c, Spacing::Joint, PhantomData)
+}
+
+/// Parses a punctuation token equal to `c` that is not joined to the following token (e.g. the `<` in `< -`).
+pub fn punct_alone<I>(c: char) -> PunctSpacing<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    PunctSpacing(c, Spacing::Alone, PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `punct_joint` and `punct_alone`.
+pub struct PunctSpacing<I>(char, Spacing, PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for PunctSpacing<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = Token;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        match uncons(input) {
+            EmptyOk(tok) | ConsumedOk(tok) => match tok {
+                Token::Punct(ref punct) if punct.as_char() == self.0 && punct.spacing() == self.1 => ConsumedOk(tok),
+                _ => EmptyErr(I::Error::empty(position).into()),
+            },
+            EmptyErr(err) => EmptyErr(err),
+            ConsumedErr(err) => ConsumedErr(err),
+        }
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        errors.error.add_expected(Info::Token(Token::Punct(proc_macro2::Punct::new(self.0, self.1))));
+    }
+}
+
+/// Parses the `>` closing a list of generic arguments, and returns its span.
+///
+/// This is `punct('>')` returning a span: a `TokenStream` has one punct per char, so operators
+/// like `>>`, `>=`, and `>>=` are already a joint `>` followed by the rest of the operator, and
+/// nothing needs to be split. E.g. in `Vec<Vec<u8>>` the first `close_angle` closes the inner
+/// list and the second closes the outer one, even if the grammar parses `>>` as an operator
+/// with `puncts(">>")` elsewhere. The rest of the operator keeps its spacing.
+pub fn close_angle<I>() -> CloseAngle<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    CloseAngle(PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `close_angle`.
+pub struct CloseAngle<I>(PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for CloseAngle<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = Span;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        match uncons(input) {
+            EmptyOk(tok) | ConsumedOk(tok) => match tok {
+                Token::Punct(ref punct) if punct.as_char() == '>' => ConsumedOk(punct.span()),
+                _ => EmptyErr(I::Error::empty(position).into()),
+            },
+            EmptyErr(err) => EmptyErr(err),
+            ConsumedErr(err) => ConsumedErr(err),
+        }
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        errors.error.add_expected(Info::Borrowed(">"));
+    }
+}
+
+/// Parses any punctuation token and returns the inner `proc_macro::Punct`.
+///
+/// Never matches delimiters, use `delim` to match those.
+pub fn any_punct<I>() -> AnyPunct<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    AnyPunct(PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `any_punct`.
+pub struct AnyPunct<I>(PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for AnyPunct<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = proc_macro2::Punct;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        match uncons(input) {
+            EmptyOk(tok) | ConsumedOk(tok) => match tok {
+                Token::Punct(punct) => ConsumedOk(punct),
+                _ => EmptyErr(I::Error::empty(position).into()),
+            },
+            EmptyErr(err) => EmptyErr(err),
+            ConsumedErr(err) => ConsumedErr(err),
+        }
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        errors.error.add_expected(Info::Borrowed("PUNCT"));
+    }
+}
+
+/// Parses a token and succeeds if `predicate` returns true for the token.
+pub fn satisfy<I, F>(predicate: F) -> Satisfy<I, F>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    F: FnMut(&Token) -> bool,
+{
+    Satisfy(predicate, PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `satisfy`.
+pub struct Satisfy<I, F>(F, PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I, F> Parser for Satisfy<I, F>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    F: FnMut(&Token) -> bool,
+{
+    type Input = I;
+    type Output = Token;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        match uncons(input) {
+            EmptyOk(tok) | ConsumedOk(tok) => if (self.0)(&tok) {
+                ConsumedOk(tok)
+            } else {
+                EmptyErr(I::Error::empty(position).into())
+            },
+            EmptyErr(err) => EmptyErr(err),
+            ConsumedErr(err) => ConsumedErr(err),
+        }
+    }
+}
+
+/// Parses a token and succeeds if `map` returns `Some` for the token, returning the mapped value.
+pub fn satisfy_map<I, F, T>(map: F) -> SatisfyMap<I, F>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    F: FnMut(&Token) -> Option<T>,
+{
+    SatisfyMap(map, PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `satisfy_map`.
+pub struct SatisfyMap<I, F>(F, PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I, F, T> Parser for SatisfyMap<I, F>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    F: FnMut(&Token) -> Option<T>,
+{
+    type Input = I;
+    type Output = T;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        match uncons(input) {
+            EmptyOk(tok) | ConsumedOk(tok) => match (self.0)(&tok) {
+                Some(value) => ConsumedOk(value),
+                None => EmptyErr(I::Error::empty(position).into()),
+            },
+            EmptyErr(err) => EmptyErr(err),
+            ConsumedErr(err) => ConsumedErr(err),
+        }
+    }
+}
+
+/// Parses a sequence of punctuation tokens matching the chars of `op` (e.g. `"->"` or `"::"`).
+///
+/// Every token except the last must have `Spacing::Joint`, so `=>` is accepted but `= >` is not.
+/// Fails without consuming input if the sequence doesn't match, which requires the input to
+/// support at least `op.len()` tokens of lookahead (see `Input::with_lookahead`).
+pub fn puncts<I>(op: &'static str) -> Puncts<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    Puncts(op, PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `puncts`.
+pub struct Puncts<I>(&'static str, PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for Puncts<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = Vec<proc_macro2::Punct>;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        parse_joint_puncts(input, self.0.chars())
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        errors.error.add_expected(Info::Borrowed(self.0));
+    }
+}
+
+/// Parses a sequence of joint punctuation equal to `op` (see `puncts`) as a single operator,
+/// such as `..=`, `...`, or `<<=`, and returns one span covering the whole operator.
+///
+/// The span is only the span of the first punct unless `Span::join` is available (see
+/// `spanned`).
+pub fn op_seq<I>(op: &'static str) -> impl Parser<Input = I, Output = Span>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    puncts(op).map(|puncts| {
+        let first = puncts.first().map_or_else(Span::call_site, |punct| punct.span());
+        let last = puncts.last().map_or(first, |punct| punct.span());
+        join_spans(first, last)
+    })
+}
+
+/// The punctuation of Rust, including multi-char operators (see `op`).
+pub const RUST_OPERATORS: &[&str] = &[
+    "+", "-", "*", "/", "%", "^", "!", "&", "|", "&&", "||", "<<", ">>", "+=", "-=", "*=", "/=",
+    "%=", "^=", "&=", "|=", "<<=", ">>=", "=", "==", "!=", ">", "<", ">=", "<=", "@", ".", "..",
+    "...", "..=", ",", ";", ":", "::", "->", "=>", "#", "$", "?", "~",
+];
+
+/// Parses the Rust operator `op` (e.g. `"+="` or `"->"`) and returns its span.
+///
+/// Single-char operators are matched like `punct` and multi-char operators like `op_seq`.
+///
+/// # Panics
+/// Panics if `op` is not one of `RUST_OPERATORS`.
+pub fn op<I>(op: &'static str) -> impl Parser<Input = I, Output = Span>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    assert!(RUST_OPERATORS.contains(&op), "`{}` is not a Rust operator", op);
+    op_seq(op)
+}
+
+/// Parses an optional punctuation token equal to `c` and returns whether it was present.
+///
+/// Useful for optional terminators (e.g. a trailing `;` on the last statement). Never fails;
+/// when the punct is absent no input is consumed.
+pub fn maybe_punct<I>(c: char) -> impl Parser<Input = I, Output = bool>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    optional(punct(c)).map(|tok| tok.is_some())
+}
+
+/// Parses an optional sequence of joint punctuation equal to `op` (see `puncts`) and returns
+/// whether it was present.
+pub fn maybe_puncts<I>(op: &'static str) -> impl Parser<Input = I, Output = bool>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    optional(puncts(op)).map(|puncts| puncts.is_some())
+}
+
+/// Consumes one punct per char in `chars`, requiring joint spacing between them.
+///
+/// Resets the input if any token doesn't match, so the parse is all-or-nothing.
+fn parse_joint_puncts<I, C>(input: &mut I, chars: C) -> ConsumedResult<Vec<proc_macro2::Punct>, I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    C: Iterator<Item = char>,
+{
+    let position = input.position();
+    let checkpoint = input.checkpoint();
+    let mut puncts: Vec<proc_macro2::Punct> = Vec::new();
+    for c in chars {
+        let joint = match puncts.last() {
+            Some(prev) => prev.spacing() == Spacing::Joint,
+            None => true,
+        };
+        match uncons(input) {
+            EmptyOk(Token::Punct(punct)) | ConsumedOk(Token::Punct(punct)) if joint && punct.as_char() == c => {
+                puncts.push(punct);
+            }
+            _ => {
+                input.reset(checkpoint);
+                return EmptyErr(I::Error::empty(position).into());
+            }
+        }
+    }
+    ConsumedOk(puncts)
+}
+
+
+/// Parses a delimiter if it's char representation is equal to `c`.
+pub fn delim<I>(c: char) -> Delim<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    Delim(c, PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `delim`.
+pub struct Delim<I>(char, PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for Delim<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = Token;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        match uncons(input) {
+            EmptyOk(tok) | ConsumedOk(tok) => match tok {
+                Token::Delim(ch, _) if ch == self.0 => ConsumedOk(tok),
+                _ => EmptyErr(I::Error::empty(position).into()),
+            },
+            EmptyErr(err) => EmptyErr(err),
+            ConsumedErr(err) => ConsumedErr(err),
+        }
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        errors.error.add_expected(Info::Token(Token::Delim(self.0, proc_macro2::Span::call_site())));
+    }
+}
+
+/// Parses a delimiter equal to `c` like `delim`, but returns the span of the delimiter token.
+///
+/// Note the span of a `Token::Delim` is the span of its whole group, unless `Group::span_open`
+/// is supported (see `Token::Delim`).
+pub fn delim_span<I>(c: char) -> DelimSpan<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    DelimSpan(c, PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `delim_span`.
+pub struct DelimSpan<I>(char, PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for DelimSpan<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = Span;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        match uncons(input) {
+            EmptyOk(tok) | ConsumedOk(tok) => match tok {
+                Token::Delim(ch, span) if ch == self.0 => ConsumedOk(span),
+                _ => EmptyErr(I::Error::empty(position).into()),
+            },
+            EmptyErr(err) => EmptyErr(err),
+            ConsumedErr(err) => ConsumedErr(err),
+        }
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        errors.error.add_expected(Info::Token(Token::Delim(self.0, proc_macro2::Span::call_site())));
+    }
+}
+
+/// Parses any open delimiter (i.e. `(`, `[`, or `{`) and returns which one along with its span.
+///
+/// Note the span of a `Token::Delim` is the span of its whole group, unless `Group::span_open`
+/// is supported (see `Token::Delim`).
+pub fn any_delim<I>() -> AnyDelim<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    AnyDelim(PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `any_delim`.
+pub struct AnyDelim<I>(PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for AnyDelim<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = (proc_macro2::Delimiter, Span);
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        match uncons(input) {
+            EmptyOk(tok) | ConsumedOk(tok) => match tok {
+                Token::Delim('(', span) => ConsumedOk((proc_macro2::Delimiter::Parenthesis, span)),
+                Token::Delim('[', span) => ConsumedOk((proc_macro2::Delimiter::Bracket, span)),
+                Token::Delim('{', span) => ConsumedOk((proc_macro2::Delimiter::Brace, span)),
+                _ => EmptyErr(I::Error::empty(position).into()),
+            },
+            EmptyErr(err) => EmptyErr(err),
+            ConsumedErr(err) => ConsumedErr(err),
+        }
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        for c in &['(', '[', '{'] {
+            errors.error.add_expected(Info::Token(Token::Delim(*c, Span::call_site())));
+        }
+    }
+}
+
+/// Parses any token (including delimiters) and returns it.
+///
+/// Only fails at the end of input.
+pub fn any_token<I>() -> AnyToken<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    AnyToken(PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `any_token`.
+pub struct AnyToken<I>(PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for AnyToken<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = Token;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        match uncons(input) {
+            EmptyOk(tok) | ConsumedOk(tok) => ConsumedOk(tok),
+            EmptyErr(err) => EmptyErr(err),
+            ConsumedErr(err) => ConsumedErr(err),
+        }
+    }
+}
+
+/// Returns a clone of the next token without consuming it, or `None` at the end of input.
+///
+/// Never fails, so it can be used to inspect the input before choosing how to continue
+/// (e.g. with `then`).
+pub fn peek_token<I>() -> PeekToken<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    PeekToken(PhantomData)
+}
+
+/// Returns the char of the next token without consuming it if it's a punct or delimiter,
+/// otherwise `None` (see `peek_token`).
+pub fn peek_char<I>() -> impl Parser<Input = I, Output = Option<char>>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    peek_token().map(|tok: Option<Token>| tok.and_then(|tok| tok.to_char()))
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `peek_token`.
+pub struct PeekToken<I>(PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for PeekToken<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = Option<Token>;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let checkpoint = input.checkpoint();
+        let next = input.uncons().ok();
+        input.reset(checkpoint);
+        EmptyOk(next)
+    }
+}
+
+/// Succeeds only if there are no more tokens in the input, including closing delimiters.
+pub fn eof<I>() -> Eof<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    Eof(PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `eof`.
+pub struct Eof<I>(PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for Eof<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = ();
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        let checkpoint = input.checkpoint();
+        match input.uncons() {
+            Err(ref err) if *err == StreamError::end_of_input() => EmptyOk(()),
+            Err(_) => {
+                input.reset(checkpoint);
+                EmptyErr(I::Error::empty(position).into())
+            }
+            Ok(tok) => {
+                input.reset(checkpoint);
+                EmptyErr(I::Error::from_error(position, StreamError::unexpected_token(tok)).into())
+            }
+        }
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        errors.error.add_expected(Info::Borrowed("end of input"));
+    }
+}
+
+/// Succeeds without consuming input if the next token is a close delimiter or there are no
+/// more tokens, i.e. at the end of the current group.
+///
+/// Useful to end a loop over the items of a block, e.g. `(many(item()), end_of_group())`, or to
+/// check whether more items follow with `optional(end_of_group())`.
+pub fn end_of_group<I>() -> EndOfGroup<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    EndOfGroup(PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `end_of_group`.
+pub struct EndOfGroup<I>(PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for EndOfGroup<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = ();
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        let checkpoint = input.checkpoint();
+        let next = input.uncons();
+        input.reset(checkpoint);
+        match next {
+            Err(ref err) if *err == StreamError::end_of_input() => EmptyOk(()),
+            Err(_) => EmptyErr(I::Error::empty(position).into()),
+            Ok(Token::Delim(')', _)) | Ok(Token::Delim(']', _)) | Ok(Token::Delim('}', _)) => EmptyOk(()),
+            Ok(tok) => EmptyErr(I::Error::from_error(position, StreamError::unexpected_token(tok)).into()),
+        }
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        errors.error.add_expected(Info::Borrowed("end of group"));
+    }
+}
+
+/// Succeeds without consuming input unless the next token is equal to `tok`.
+///
+/// Unlike `combine::not_followed_by`, the error reports the token itself as unexpected
+/// (e.g. ``Unexpected `(` ``), which is useful for negative lookahead like an ident that
+/// must not be followed by `(`.
+pub fn not_followed_by_token<I>(tok: Token) -> NotFollowedByToken<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    NotFollowedByToken(tok, PhantomData)
+}
+
+/// Succeeds without consuming input unless the next token is a punct or delimiter equal to `c`
+/// (see `not_followed_by_token`).
+pub fn not_followed_by_punct<I>(c: char) -> NotFollowedByToken<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    let tok = match c {
+        '(' | ')' | '[' | ']' | '{' | '}' => Token::Delim(c, Span::call_site()),
+        _ => Token::Punct(proc_macro2::Punct::new(c, Spacing::Alone)),
+    };
+    NotFollowedByToken(tok, PhantomData)
+}
+
+#[derive(Clone)]
+/// Represents the return type of `not_followed_by_token` and `not_followed_by_punct`.
+pub struct NotFollowedByToken<I>(Token, PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for NotFollowedByToken<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = ();
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        let checkpoint = input.checkpoint();
+        let next = input.uncons();
+        input.reset(checkpoint);
+        match next {
+            Ok(tok) if tok == self.0 => EmptyErr(I::Error::from_error(position, StreamError::unexpected_token(tok)).into()),
+            _ => EmptyOk(()),
+        }
+    }
+}
+
+/// Parses a lifetime (i.e. a `'` joined to an ident, like `'a`).
+///
+/// Fails without consuming input if the tokens don't form a lifetime.
+pub fn lifetime<I>() -> Lifetime<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    Lifetime(PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `lifetime`.
+pub struct Lifetime<I>(PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for Lifetime<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = syntax::Lifetime;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        let checkpoint = input.checkpoint();
+        if let Ok(Token::Punct(apostrophe)) = input.uncons() {
+            if apostrophe.as_char() == '\'' && apostrophe.spacing() == Spacing::Joint {
+                if let Ok(Token::Ident(ident)) = input.uncons() {
+                    return ConsumedOk(syntax::Lifetime { apostrophe: apostrophe.span(), ident });
+                }
+            }
+        }
+        input.reset(checkpoint);
+        EmptyErr(I::Error::empty(position).into())
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        errors.error.add_expected(Info::Borrowed("LIFETIME"));
+    }
+}
+
+/// Parses a path of idents separated by `::` (e.g. `foo::bar::Baz`), with an optional leading `::`.
+///
+/// The path ends before a `::` which isn't followed by an ident, so e.g. only `foo` of `foo::<T>`
+/// or `foo::*` is parsed. Requires a lookahead of at least 3 tokens.
+pub fn path<I>() -> impl Parser<Input = I, Output = syntax::Path>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    (optional(puncts("::")), ident(), many(attempt((puncts("::"), ident()).map(|(_, ident)| ident))))
+        .map(|(leading, first, rest): (_, _, Vec<_>)| {
+            let mut segments = vec![first];
+            segments.extend(rest);
+            syntax::Path { leading_colon: leading.is_some(), segments }
+        })
+}
+
+/// Parses a `name = value` pair and returns the name and the output of `value`.
+///
+/// Once the name is parsed a missing `=` is an error (e.g. "Expected `=`").
+pub fn key_value<I, P>(value: P) -> impl Parser<Input = I, Output = (proc_macro2::Ident, P::Output)>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    P: Parser<Input = I>,
+{
+    (ident(), tokens::eq(), value).map(|(key, _, value)| (key, value))
+}
+
+/// Parses a `name: value` pair like `key_value`, but separated by `:` instead of `=`.
+pub fn key_value_colon<I, P>(value: P) -> impl Parser<Input = I, Output = (proc_macro2::Ident, P::Output)>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    P: Parser<Input = I>,
+{
+    (ident(), tokens::colon(), value).map(|(key, _, value)| (key, value))
+}
+
+/// Parses idents separated by `.` (e.g. `server.tls.cert`).
+///
+/// A `.` is only consumed when it's followed by another segment, so the path stops before a
+/// trailing `.`, a range like `..`, or a method call like `.len()` (a segment followed by
+/// `(`). Requires at least 3 tokens of lookahead (see `Input::with_lookahead`).
+pub fn dotted_path<I>() -> impl Parser<Input = I, Output = Vec<proc_macro2::Ident>>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    let segment = attempt(tokens::dot().with(ident()).skip(not_followed_by_punct('(')));
+    (ident(), many(segment)).map(|(first, rest): (_, Vec<_>)| {
+        let mut segments = vec![first];
+        segments.extend(rest);
+        segments
+    })
+}
+
+/// Parses an attribute argument: a path (`flag`), a path with a literal value (`key = "value"`),
+/// a path with a nested list of arguments (`feature(any, all)`), or a bare literal.
+///
+/// Use `comma_separated(meta())` to parse the arguments of a `#[proc_macro_attribute]`.
+pub fn meta<I>() -> impl Parser<Input = I, Output = syntax::Meta>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    combine::parser(parse_meta::<I>)
+}
+
+/// Parses a single `meta` (split out of `meta` so the parser can be recursive).
+fn parse_meta<I>(input: &mut I) -> ParseResult<syntax::Meta, I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    enum Args {
+        Value(proc_macro2::Literal),
+        List(Vec<syntax::Meta>),
+    }
+    let args = tokens::eq().with(literal()).map(Args::Value)
+        .or(parenthesized(comma_separated(meta())).map(Args::List));
+    let path_meta = (path(), optional(args)).map(|(path, args)| match args {
+        None => syntax::Meta::Path(path),
+        Some(Args::Value(value)) => syntax::Meta::NameValue(path, value),
+        Some(Args::List(nested)) => syntax::Meta::List(path, nested),
+    });
+    literal().map(syntax::Meta::Literal).or(path_meta).parse_stream(input)
+}
+
+/// Parses an outer attribute (e.g. `#[doc = "..."]` or `#[my_attr(a, b)]`).
+///
+/// The tokens after the attribute's path are returned without being parsed.
+pub fn attribute<I>() -> impl Parser<Input = I, Output = syntax::Attribute>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    (punct('#'), delim('['), path(), balanced_until_close(), delim(']'))
+        .map(|(pound, _, path, tokens, _)| syntax::Attribute { pound: pound.span(), path, tokens })
+}
+
+/// Parses a doc comment, which reaches the macro as a `#[doc = "..."]` attribute.
+///
+/// Returns the documentation string and the span of the string literal.
+pub fn doc_comment<I>() -> impl Parser<Input = I, Output = (String, Span)>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    (punct('#'), delim('['), keyword("doc"), punct('='), string_lit(), delim(']'))
+        .map(|(_, _, _, _, doc, _)| doc)
+}
+
+/// Parses a visibility (e.g. `pub`, `pub(crate)`, or `pub(in some::path)`).
+///
+/// Returns `Visibility::Inherited` without consuming input if there is no `pub`. A `pub` followed
+/// by a parenthesized type (e.g. `pub (u8, u8)`) is only recognized with a lookahead of at least 3.
+pub fn visibility<I>() -> impl Parser<Input = I, Output = syntax::Visibility>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    let restriction = keyword_any(&["crate", "self", "super"])
+        .map(|(_, word)| syntax::Path { leading_colon: false, segments: vec![word] })
+        .or(keyword("in").with(path()));
+    let vis = keyword_ident("pub")
+        .and(optional(attempt((delim('('), restriction, delim(')')))))
+        .map(|(pub_token, restriction)| match restriction {
+            Some((_, path, _)) => syntax::Visibility::Restricted(pub_token, path),
+            None => syntax::Visibility::Public(pub_token),
+        });
+    optional(vis).map(|vis| vis.unwrap_or(syntax::Visibility::Inherited))
+}
+
+/// Parses a single token tree, i.e. one token or a whole delimited group (e.g. `(a, [b])`).
+///
+/// Fails without consuming input if the next token is a close delimiter.
+pub fn tt<I>() -> Tt<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    Tt(PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `tt`.
+pub struct Tt<I>(PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for Tt<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = TokenTree;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        parse_token_tree(input, false)
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        errors.error.add_expected(Info::Borrowed("TOKEN_TREE"));
+    }
+}
+
+/// Parses a delimited group (e.g. `{ ... }`) and returns it without flattening its contents.
+///
+/// Use `Group::stream` to get the inner tokens and `Group::span` to get the span of the group.
+/// Also parses a `Token::Group`, so this is how groups are matched in an `Input::shallow` input.
+pub fn group<I>() -> Group<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    Group(PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `group`.
+pub struct Group<I>(PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for Group<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = proc_macro2::Group;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        parse_token_tree(input, true).map(|tree| match tree {
+            TokenTree::Group(group) => group,
+            _ => unreachable!("`parse_token_tree` only returns groups when `groups_only` is set"),
+        })
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        errors.error.add_expected(Info::Borrowed("GROUP"));
+    }
+}
+
+/// Parses a delimited group and returns a new `Input` over only the tokens inside the group.
+///
+/// The returned input can be parsed independently (e.g. with a different grammar), and its
+/// trailing tokens checked with `Incomplete::from_stream` separately from the outer input.
+/// The returned input is created with `Input::enter_group`, so it keeps the outer input's
+/// options (see `GroupInput`).
+pub fn nested<I>() -> impl Parser<Input = I, Output = Input>
+where
+    I: Stream<Item = Token> + GroupInput,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    combine::parser(|input: &mut I| {
+        let (group, consumed) = group().parse_stream(input)?;
+        Ok((input.enter_group(&group), consumed))
+    })
+}
+
+/// Parses `inner` between a `{` and its matching `}`.
+pub fn braced<I, P>(inner: P) -> impl Parser<Input = I, Output = P::Output>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    P: Parser<Input = I>,
+{
+    between(delim('{'), delim('}'), inner)
+}
+
+/// Parses `inner` between a `(` and its matching `)`.
+pub fn parenthesized<I, P>(inner: P) -> impl Parser<Input = I, Output = P::Output>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    P: Parser<Input = I>,
+{
+    between(delim('('), delim(')'), inner)
+}
+
+/// Parses `inner` between a `[` and its matching `]`.
+pub fn bracketed<I, P>(inner: P) -> impl Parser<Input = I, Output = P::Output>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    P: Parser<Input = I>,
+{
+    between(delim('['), delim(']'), inner)
+}
+
+/// Parses `inner` between any open delimiter (see `any_delim`) and its matching close
+/// delimiter, e.g. `(...)`, `[...]`, or `{...}` like the arguments of a macro invocation.
+///
+/// Returns which delimiter was used along with the output of `inner`. Once the open delimiter
+/// is parsed, a missing close delimiter is reported as the expected matching closer.
+pub fn matched_group<I, P>(inner: P) -> MatchedGroup<I, P>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    P: Parser<Input = I>,
+{
+    MatchedGroup(any_delim(), inner)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `matched_group`.
+pub struct MatchedGroup<I, P>(AnyDelim<I>, P)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I, P> Parser for MatchedGroup<I, P>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    P: Parser<Input = I>,
+{
+    type Input = I;
+    type Output = (proc_macro2::Delimiter, P::Output);
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let delimiter = match self.0.parse_lazy(input) {
+            EmptyOk((delimiter, _)) | ConsumedOk((delimiter, _)) => delimiter,
+            EmptyErr(err) => return EmptyErr(err),
+            ConsumedErr(err) => return ConsumedErr(err),
+        };
+        let output = match self.1.parse_stream_consumed(input) {
+            EmptyOk(output) | ConsumedOk(output) => output,
+            EmptyErr(err) => return ConsumedErr(err.error),
+            ConsumedErr(err) => return ConsumedErr(err),
+        };
+        let close = match delimiter {
+            proc_macro2::Delimiter::Parenthesis => ')',
+            proc_macro2::Delimiter::Bracket => ']',
+            _ => '}',
+        };
+        match delim(close).parse_stream_consumed(input) {
+            EmptyOk(_) | ConsumedOk(_) => ConsumedOk((delimiter, output)),
+            EmptyErr(err) => ConsumedErr(err.error),
+            ConsumedErr(err) => ConsumedErr(err),
+        }
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        self.0.add_error(errors);
+    }
+}
+
+/// Parses zero or more `item`s separated by `separator`, allowing an optional trailing separator.
+///
+/// For example `punctuated(ident(), punct(','))` accepts `a, b, c` as well as `a, b, c,`.
+pub fn punctuated<I, P, S>(item: P, separator: S) -> impl Parser<Input = I, Output = Vec<P::Output>>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    P: Parser<Input = I>,
+    S: Parser<Input = I>,
+{
+    sep_end_by(item, separator)
+}
+
+/// Parses one or more `item`s separated by `separator`, allowing an optional trailing separator.
+///
+/// An empty list is an error at the token where the first `item` was expected, which expects
+/// "at least one" of what `item` expects (e.g. "Expected `at least one IDENT`").
+pub fn punctuated1<I, P, S>(item: P, separator: S) -> Punctuated1<I, P, S>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    P: Parser<Input = I>,
+    S: Parser<Input = I>,
+{
+    Punctuated1(sep_end_by1(item, separator), None)
+}
+
+#[derive(Clone)]
+/// Represents the return type of `punctuated1`.
+pub struct Punctuated1<I, P, S>(SepEndBy1<Vec<P::Output>, P, S>, Option<String>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    P: Parser<Input = I>,
+    S: Parser<Input = I>;
+
+impl<I, P, S> Parser for Punctuated1<I, P, S>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    P: Parser<Input = I>,
+    S: Parser<Input = I>,
+{
+    type Input = I;
+    type Output = Vec<P::Output>;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        self.1 = None;
+        match self.0.parse_lazy(input) {
+            EmptyErr(err) => {
+                // Describe the missing item by what it expects, for `add_error`
+                let mut item = Tracked::from(I::Error::empty(position));
+                self.0.add_error(&mut item);
+                let errors: easy::Errors<Token, I::Range, I::Position> = item.error.into_other();
+                let expected: Vec<String> = errors.errors.into_iter().filter_map(|err| match err {
+                    easy::Error::Expected(easy::Info::Token(tok)) => Some(format!("`{}`", tok)),
+                    easy::Error::Expected(easy::Info::Owned(info)) => Some(info),
+                    easy::Error::Expected(easy::Info::Borrowed(info)) => Some(info.to_string()),
+                    _ => None,
+                }).collect();
+                self.1 = Some(if expected.is_empty() { "item".to_string() } else { expected.join(" or ") });
+                EmptyErr(err)
+            }
+            result => result,
+        }
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        match self.1 {
+            Some(ref item) => {
+                let expected = StreamError::expected_message(format_args!("at least one {}", item));
+                ParseError::set_expected(errors, expected, |errors| self.0.add_error(errors));
+            }
+            None => self.0.add_error(errors),
+        }
+    }
+}
+
+/// Parses zero or more `item`s separated by commas, allowing a trailing comma.
+pub fn comma_separated<I, P>(item: P) -> impl Parser<Input = I, Output = Vec<P::Output>>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    P: Parser<Input = I>,
+{
+    punctuated(item, punct(','))
+}
+
+/// Parses zero or more `item`s until the close delimiter of the current group (or the end of
+/// input), e.g. the statements in `braced(many_until_close(statement()))`.
+///
+/// The close delimiter is not consumed. Unlike `many`, an `item` that fails without consuming
+/// input is an error (unless the current group has ended), so errors are reported at the
+/// first bad item rather than as a missing close delimiter, and `item` doesn't need `attempt`.
+pub fn many_until_close<I, P>(item: P) -> ManyUntilClose<P>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    P: Parser<Input = I>,
+{
+    ManyUntilClose(item)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `many_until_close`.
+pub struct ManyUntilClose<P>(P);
+
+impl<I, P> Parser for ManyUntilClose<P>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    P: Parser<Input = I>,
+{
+    type Input = I;
+    type Output = Vec<P::Output>;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let mut items = Vec::new();
+        let mut consumed = false;
+        loop {
+            let checkpoint = input.checkpoint();
+            let at_close = match input.uncons() {
+                Ok(Token::Delim(')', _)) | Ok(Token::Delim(']', _)) | Ok(Token::Delim('}', _)) => true,
+                Ok(_) => false,
+                Err(_) => true,
+            };
+            input.reset(checkpoint);
+            if at_close {
+                break;
+            }
+            match self.0.parse_stream_consumed(input) {
+                ConsumedOk(item) => {
+                    items.push(item);
+                    consumed = true;
+                }
+                EmptyOk(item) => {
+                    items.push(item);
+                    // Stop rather than loop forever on an item that matches nothing
+                    break;
+                }
+                EmptyErr(err) => return if consumed { ConsumedErr(err.error) } else { EmptyErr(err) },
+                ConsumedErr(err) => return ConsumedErr(err),
+            }
+        }
+        if consumed { ConsumedOk(items) } else { EmptyOk(items) }
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        self.0.add_error(errors);
+    }
+}
+
+/// Parses zero or more `item`s separated by semicolons, allowing a trailing semicolon.
+pub fn semicolon_separated<I, P>(item: P) -> impl Parser<Input = I, Output = Vec<P::Output>>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    P: Parser<Input = I>,
+{
+    punctuated(item, punct(';'))
+}
+
+/// Parses a braced list of fields like `{ name: Type, other: Vec<(u8, u8)>, }`, allowing a
+/// trailing comma.
+///
+/// Returns each field's name and type, where the type is captured as opaque tokens up to the
+/// next `,` that isn't nested within a group or `<..>`.
+pub fn field_list<I>() -> impl Parser<Input = I, Output = Vec<(proc_macro2::Ident, proc_macro2::TokenStream)>>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    braced(comma_separated((ident(), tokens::colon(), opaque_type()).map(|(name, _, ty)| (name, ty))))
+}
+
+/// Parses each of the tuple of `clauses` at most once, in any order, and returns a tuple with
+/// the output of each clause that was present (e.g. `permutation((where_clause(), limit()))`).
+///
+/// Stops when none of the remaining clauses match. Each clause should fail without consuming
+/// input when it's absent (use `attempt` for multi-token prefixes); check for required clauses
+/// by matching on the returned `Option`s.
+///
+/// When the parser that follows fails, the error also lists what each clause that wasn't
+/// present expected, the same way `choice` merges the errors of its alternatives.
+pub fn permutation<P>(clauses: P) -> Permutation<P> {
+    Permutation(clauses, [None; 8])
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `permutation`.
+///
+/// Also keeps, for each clause that wasn't present in the last parse, the error offset it failed
+/// with, so its expected error can be added later.
+pub struct Permutation<P>(P, [Option<ErrorOffset>; 8]);
+
+macro_rules! impl_permutation {
+    ($($P:ident $index:tt),+) => {
+        impl<I, $($P),+> Parser for Permutation<($($P,)+)>
+        where
+            I: Stream<Item = Token>,
+            I::Error: ParseError<I::Item, I::Range, I::Position>,
+            $($P: Parser<Input = I>),+
+        {
+            type Input = I;
+            type Output = ($(Option<$P::Output>,)+);
+            type PartialState = ();
+
+            fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+                let mut outputs: Self::Output = Default::default();
+                let mut consumed = false;
+                self.1 = [None; 8];
+                loop {
+                    let mut matched = false;
+                    $(
+                        if outputs.$index.is_none() {
+                            let checkpoint = input.checkpoint();
+                            match (self.0).$index.parse_lazy(input) {
+                                ConsumedOk(output) => {
+                                    outputs.$index = Some(output);
+                                    self.1[$index] = None;
+                                    consumed = true;
+                                    matched = true;
+                                }
+                                EmptyOk(output) => {
+                                    outputs.$index = Some(output);
+                                    self.1[$index] = None;
+                                    matched = true;
+                                }
+                                EmptyErr(err) => {
+                                    self.1[$index] = Some(err.offset);
+                                    input.reset(checkpoint);
+                                }
+                                ConsumedErr(err) => return ConsumedErr(err),
+                            }
+                        }
+                    )+
+                    if !matched {
+                        break;
+                    }
+                }
+                if consumed { ConsumedOk(outputs) } else { EmptyOk(outputs) }
+            }
+
+            fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+                self.add_missing_errors(errors);
+            }
+
+            fn add_consumed_expected_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+                self.add_missing_errors(errors);
+            }
+        }
+
+        impl<I, $($P),+> Permutation<($($P,)+)>
+        where
+            I: Stream<Item = Token>,
+            I::Error: ParseError<I::Item, I::Range, I::Position>,
+            $($P: Parser<Input = I>),+
+        {
+            // Adds the errors of the clauses that weren't present, like `choice` does for each of
+            // its alternatives, using the offset each clause failed with.
+            fn add_missing_errors(&mut self, errors: &mut Tracked<I::Error>) {
+                let before = errors.offset;
+                $(
+                    if let Some(offset) = self.1[$index] {
+                        errors.offset = offset;
+                        (self.0).$index.add_error(errors);
+                    }
+                )+
+                errors.offset = before;
+            }
+        }
+    };
+}
+
+impl_permutation!(A 0);
+impl_permutation!(A 0, B 1);
+impl_permutation!(A 0, B 1, C 2);
+impl_permutation!(A 0, B 1, C 2, D 3);
+impl_permutation!(A 0, B 1, C 2, D 3, E 4);
+impl_permutation!(A 0, B 1, C 2, D 3, E 4, F 5);
+impl_permutation!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_permutation!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+
+/// Parses `inner` and returns its output with a span covering the tokens it consumed.
+///
+/// The span is only the span of the first token unless `Span::join` is available (see
+/// `proc_macro2`'s `procmacro2_semver_exempt`). Requires the stream's positions to know
+/// the span of the last consumed token, which is the case for `Input::with_lookahead`.
+pub fn spanned<I, P>(inner: P) -> impl Parser<Input = I, Output = (P::Output, Span)>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    I::Position: Into<Span>,
+    P: Parser<Input = I>,
+{
+    (position(), optional(look_ahead(any_token())), inner, position())
+        .map(|(start, first, output, end): (I::Position, Option<Token>, _, I::Position)| {
+            let first = first.map_or_else(Span::call_site, |tok| tok.span());
+            let span = if start == end { first } else { join_spans(first, end.into()) };
+            (output, span)
+        })
+}
+
+/// Parses `inner` and returns the tokens it consumed as a `TokenStream` (discarding its output).
+///
+/// Delimiters are regrouped, so the result can be emitted verbatim (e.g. with `quote!`).
+/// Fails if `inner` consumes an open delimiter without its matching close delimiter (or
+/// vice versa). The consumed tokens are read a second time after `inner` succeeds, so the
+/// stream's lookahead must be at least as large as the recognized region.
+pub fn recognize<I, P>(inner: P) -> Recognize<P>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    P: Parser<Input = I>,
+{
+    Recognize(inner)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `recognize`.
+pub struct Recognize<P>(P);
+
+impl<I, P> Parser for Recognize<P>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    P: Parser<Input = I>,
+{
+    type Input = I;
+    type Output = proc_macro2::TokenStream;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let start = input.position();
+        let checkpoint = input.checkpoint();
+        match self.0.parse_lazy(input) {
+            EmptyOk(_) => return EmptyOk(proc_macro2::TokenStream::new()),
+            ConsumedOk(_) => {}
+            EmptyErr(err) => return EmptyErr(err),
+            ConsumedErr(err) => return ConsumedErr(err),
+        }
+        let end = input.position();
+        input.reset(checkpoint);
+        let mut tokens = Vec::new();
+        while input.position() < end {
+            match input.uncons() {
+                Ok(tok) => tokens.push(tok),
+                Err(err) => return ConsumedErr(I::Error::from_error(input.position(), err)),
+            }
+        }
+        match tokens_to_stream(tokens) {
+            Ok(stream) => ConsumedOk(stream),
+            Err(_) => ConsumedErr(I::Error::from_error(start, StreamError::message_static_message("unbalanced delimiters"))),
+        }
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        self.0.add_error(errors);
+    }
+}
+
+/// Discards tokens up to (but not including) the first token for which `predicate` returns true.
+///
+/// Nested groups are skipped entirely, so `predicate` is only checked against tokens in the
+/// current group. Skipping also stops before the close delimiter of the current group and at
+/// the end of input, so this never fails. Useful for error recovery (e.g. skip to the next `;`).
+pub fn skip_until<I, F>(predicate: F) -> SkipUntil<I, F>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    F: FnMut(&Token) -> bool,
+{
+    SkipUntil(predicate, PhantomData)
+}
+
+/// Discards tokens up to (but not including) the first token equal to `tok` (see `skip_until`).
+pub fn skip_until_token<I>(tok: Token) -> impl Parser<Input = I, Output = ()>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    skip_until(move |next: &Token| *next == tok)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `skip_until`.
+pub struct SkipUntil<I, F>(F, PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I, F> Parser for SkipUntil<I, F>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    F: FnMut(&Token) -> bool,
+{
+    type Input = I;
+    type Output = ();
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        if collect_until(input, &mut self.0).is_empty() {
+            EmptyOk(())
+        } else {
+            ConsumedOk(())
+        }
+    }
+}
+
+/// Consumes all of the remaining tokens and returns them as a `TokenStream`.
+///
+/// Fails if the remaining tokens have unbalanced delimiters, which happens when used inside
+/// a group (use `rest_of_group` instead).
+pub fn rest_of_input<I>() -> RestOfInput<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    RestOfInput(PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `rest_of_input`.
+pub struct RestOfInput<I>(PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for RestOfInput<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = proc_macro2::TokenStream;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        let mut tokens = Vec::new();
+        while let Ok(tok) = input.uncons() {
+            tokens.push(tok);
+        }
+        let consumed = !tokens.is_empty();
+        match tokens_to_stream(tokens) {
+            Ok(stream) if consumed => ConsumedOk(stream),
+            Ok(stream) => EmptyOk(stream),
+            Err(_) => ConsumedErr(I::Error::from_error(position, StreamError::message_static_message("unbalanced delimiters"))),
+        }
+    }
+}
+
+/// Consumes tokens up to (but not including) the first token for which `predicate` returns
+/// true, and returns them as a `TokenStream`.
+///
+/// Like `skip_until`, nested groups are consumed entirely (so a `;` inside braces doesn't end
+/// the capture), and capturing also stops before the close delimiter of the current group
+/// and at the end of input.
+pub fn take_until<I, F>(predicate: F) -> TakeUntil<I, F>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    F: FnMut(&Token) -> bool,
+{
+    TakeUntil(predicate, PhantomData)
+}
+
+/// Consumes tokens up to (but not including) the first punct equal to `c` that isn't nested in
+/// a group, and returns them as a `TokenStream` (see `take_until`).
+pub fn take_until_punct<I>(c: char) -> impl Parser<Input = I, Output = proc_macro2::TokenStream>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    take_until(move |tok: &Token| match tok {
+        Token::Punct(punct) => punct.as_char() == c,
+        _ => false,
+    })
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `take_until`.
+pub struct TakeUntil<I, F>(F, PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I, F> Parser for TakeUntil<I, F>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    F: FnMut(&Token) -> bool,
+{
+    type Input = I;
+    type Output = proc_macro2::TokenStream;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        let tokens = collect_until(input, &mut self.0);
+        let consumed = !tokens.is_empty();
+        match tokens_to_stream(tokens) {
+            Ok(stream) if consumed => ConsumedOk(stream),
+            Ok(stream) => EmptyOk(stream),
+            Err(_) => ConsumedErr(I::Error::from_error(position, StreamError::message_static_message("unbalanced delimiters"))),
+        }
+    }
+}
+
+/// Consumes the remaining tokens of the current group and returns them as a `TokenStream`.
+///
+/// Stops just before the close delimiter of the current group (or at the end of input), and
+/// consumes nested groups entirely. Useful for opaque regions like embedded Rust expressions.
+pub fn balanced_until_close<I>() -> BalancedUntilClose<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    BalancedUntilClose(PhantomData)
+}
+
+/// Consumes the remaining tokens of the current group and returns them as a `TokenStream`,
+/// e.g. the opaque body in `(header(), rest_of_group())`.
+///
+/// This is the same parser as `balanced_until_close`.
+pub fn rest_of_group<I>() -> BalancedUntilClose<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    balanced_until_close()
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `balanced_until_close`.
+pub struct BalancedUntilClose<I>(PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for BalancedUntilClose<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = proc_macro2::TokenStream;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        let tokens = collect_until_close(input);
+        let consumed = !tokens.is_empty();
+        match tokens_to_stream(tokens) {
+            Ok(stream) if consumed => ConsumedOk(stream),
+            Ok(stream) => EmptyOk(stream),
+            Err(_) => ConsumedErr(I::Error::from_error(position, StreamError::message_static_message("unbalanced delimiters"))),
+        }
+    }
+}
+
+/// Consumes the tokens of a type up to the next `,` or close delimiter that isn't nested
+/// within a group or `<..>`, and returns them as a `TokenStream`.
+fn opaque_type<I>() -> OpaqueType<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    OpaqueType(PhantomData)
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of `opaque_type`.
+struct OpaqueType<I>(PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for OpaqueType<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = proc_macro2::TokenStream;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        let mut tokens = Vec::new();
+        let (mut depth, mut angles) = (0usize, 0usize);
+        let mut after_dash = false;
+        loop {
+            let checkpoint = input.checkpoint();
+            let tok = match input.uncons() {
+                Ok(tok) => tok,
+                Err(_) => break,
+            };
+            match tok {
+                Token::Delim('(', _) | Token::Delim('[', _) | Token::Delim('{', _) => depth += 1,
+                Token::Delim(_, _) if depth == 0 => {
+                    input.reset(checkpoint);
+                    break;
+                }
+                Token::Delim(_, _) => depth -= 1,
+                Token::Punct(ref punct) if depth == 0 => match punct.as_char() {
+                    ',' if angles == 0 => {
+                        input.reset(checkpoint);
+                        break;
+                    }
+                    '<' => angles += 1,
+                    '>' if !after_dash && angles > 0 => angles -= 1,
+                    _ => {}
+                },
+                _ => {}
+            }
+            after_dash = match tok {
+                Token::Punct(ref punct) => punct.as_char() == '-' && punct.spacing() == Spacing::Joint,
+                _ => false,
+            };
+            tokens.push(tok);
+        }
+        if tokens.is_empty() {
+            return EmptyErr(I::Error::empty(position).into());
+        }
+        match tokens_to_stream(tokens) {
+            Ok(stream) => ConsumedOk(stream),
+            Err(_) => ConsumedErr(I::Error::from_error(position, StreamError::message_static_message("unbalanced delimiters"))),
+        }
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        errors.error.add_expected(Info::Borrowed("TYPE"));
+    }
+}
+
+/// Consumes and returns the tokens up to the close delimiter of the current group (see `balanced_until_close`).
+fn collect_until_close<I>(input: &mut I) -> Vec<Token>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    collect_until(input, &mut |_: &Token| false)
+}
+
+/// Consumes and returns the tokens up to the first token in the current group for which
+/// `predicate` returns true, the close delimiter of the current group, or the end of input.
+///
+/// Nested groups are consumed entirely without checking `predicate` against their tokens.
+fn collect_until<I, F>(input: &mut I, predicate: &mut F) -> Vec<Token>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    F: FnMut(&Token) -> bool,
+{
+    let mut tokens = Vec::new();
+    let mut depth = 0usize;
+    loop {
+        let checkpoint = input.checkpoint();
+        let tok = match input.uncons() {
+            Ok(tok) => tok,
+            Err(_) => break,
+        };
+        match tok {
+            ref tok if depth == 0 && predicate(tok) => {
+                input.reset(checkpoint);
+                break;
+            }
+            Token::Delim('(', _) | Token::Delim('[', _) | Token::Delim('{', _) => depth += 1,
+            Token::Delim(_, _) if depth == 0 => {
+                input.reset(checkpoint);
+                break;
+            }
+            Token::Delim(_, _) => depth -= 1,
+            _ => {}
+        }
+        tokens.push(tok);
+    }
+    tokens
+}
+
+/// Consumes a single token tree (see `tt`), or only a delimited group if `groups_only` is true.
+fn parse_token_tree<I>(input: &mut I, groups_only: bool) -> ConsumedResult<TokenTree, I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    let position = input.position();
+    let first = match uncons(input) {
+        EmptyOk(tok) | ConsumedOk(tok) => tok,
+        EmptyErr(err) => return EmptyErr(err),
+        ConsumedErr(err) => return ConsumedErr(err),
+    };
+    let mut tokens = vec![first];
+    match tokens[0] {
+        Token::Delim('(', _) | Token::Delim('[', _) | Token::Delim('{', _) => {
+            tokens.extend(collect_until_close(input));
+            match input.uncons() {
+                Ok(close) => tokens.push(close),
+                Err(err) => return ConsumedErr(I::Error::from_error(input.position(), err)),
+            }
+        }
+        Token::Delim(_, _) => return EmptyErr(I::Error::empty(position).into()),
+        Token::Group(_) => {}
+        _ if groups_only => return EmptyErr(I::Error::empty(position).into()),
+        _ => {}
+    }
+    match tokens_to_stream(tokens).ok().and_then(|stream| stream.into_iter().next()) {
+        Some(tree) => ConsumedOk(tree),
+        None => ConsumedErr(I::Error::empty(position)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{close_angle, duration_lit, ident, int_in_range, int_lit_as, kebab_ident, keyword, literal_matching, nested, path, permutation, punct, punctuated1, puncts, signed_float_lit, signed_int_lit, spanned, unit_lit, version_lit};
+    use crate::input::join_spans;
+    use crate::{Input, Token};
+    use combine::{eof, Parser, Positioned};
+
+    #[test]
+    fn whitespace_separated_units() {
+        let (name, rest) = kebab_ident().parse(Input::parse_str("font - size").unwrap()).unwrap();
+        if cfg!(procmacro2_semver_exempt) {
+            assert_eq!((name.0.as_str(), rest.len()), ("font", 2));
+        } else {
+            assert_eq!((name.0.as_str(), rest.len()), ("font-size", 0));
+        }
+        assert_eq!(duration_lit().parse(Input::parse_str("5 s").unwrap()).is_ok(), !cfg!(procmacro2_semver_exempt));
+        let ((value, unit, _), rest) = unit_lit().parse(Input::parse_str("50 %").unwrap()).unwrap();
+        if cfg!(procmacro2_semver_exempt) {
+            assert_eq!((value, unit.as_str(), rest.len()), (50.0, "", 1));
+        } else {
+            assert_eq!((value, unit.as_str(), rest.len()), (50.0, "%", 0));
+        }
+        assert_eq!(version_lit().parse(Input::parse_str("1.2 . 3").unwrap()).is_ok(), !cfg!(procmacro2_semver_exempt));
+    }
+
+    #[test]
+    fn kebab_idents() {
+        let (name, rest) = kebab_ident().parse(Input::parse_str("foo-bar-baz qux").unwrap()).unwrap();
+        assert_eq!((name.0.as_str(), rest.len()), ("foo-bar-baz", 1));
+        let (name, rest) = kebab_ident().parse(Input::parse_str("foo-1").unwrap()).unwrap();
+        assert_eq!((name.0.as_str(), rest.len()), ("foo", 2));
+        let (name, rest) = kebab_ident().parse(Input::parse_str("a -> b").unwrap()).unwrap();
+        assert_eq!((name.0.as_str(), rest.len()), ("a", 3));
+    }
+
+    // The fallback lexer rejects literals with unit suffixes (e.g. `100ms`), so the units are
+    // separate idents here, which are only accepted when whitespace can't be detected.
+    #[cfg(not(procmacro2_semver_exempt))]
+    #[test]
+    fn durations() {
+        use std::time::Duration;
+        let duration = |source| duration_lit().parse(Input::parse_str(source).unwrap()).map(|((duration, _), _)| duration);
+        assert_eq!(duration("100 ms").ok(), Some(Duration::from_millis(100)));
+        assert_eq!(duration("1.5 h").ok(), Some(Duration::from_secs(5400)));
+        assert_eq!(duration("2 s").ok(), Some(Duration::from_secs(2)));
+        assert_eq!(duration("1_000 us").ok(), Some(Duration::from_millis(1)));
+        assert!(duration("2 parsecs").is_err());
+        assert!(duration("2").is_err());
+    }
+
+    #[test]
+    fn units() {
+        let unit = |source| unit_lit().parse(Input::parse_str(source).unwrap()).map(|((value, unit, _), rest)| (value, unit, rest.len()));
+        assert_eq!(unit("50%").ok(), Some((50.0, "%".to_string(), 0)));
+        assert_eq!(unit("12.5%;").ok(), Some((12.5, "%".to_string(), 1)));
+        assert_eq!(unit("10").ok(), Some((10.0, String::new(), 0)));
+        assert!(unit("\"10\"").is_err());
+    }
+
+    #[test]
+    fn versions() {
+        let version = |source| version_lit().parse(Input::parse_str(source).unwrap()).map(|((major, minor, patch, _), rest)| (major, minor, patch, rest.len()));
+        assert_eq!(version("1.2.3").ok(), Some((1, 2, 3, 0)));
+        assert_eq!(version("0.20.10 x").ok(), Some((0, 20, 10, 1)));
+        assert!(version("1.2").is_err());
+        assert!(version("1.2.x").is_err());
+        assert!(version("1.2.3u8").is_err());
+        assert!(version("1e2.3").is_err());
+    }
+
+    #[test]
+    fn out_of_range_errors_at_literal() {
+        let err = (ident(), int_lit_as::<u8, _>()).easy_parse(Input::parse_str("x 300 y").unwrap()).err().unwrap();
+        assert_eq!(err.position.index(), 1);
+        assert!(err.to_string().contains("integer literal out of range"), "{}", err);
+        let err = (ident(), int_in_range(-10i8..=10)).easy_parse(Input::parse_str("x - 11 y").unwrap()).err().unwrap();
+        assert_eq!(err.position.index(), 1);
+        assert!(err.to_string().contains("Expected `integer in range -10..=10`"), "{}", err);
+    }
+
+    #[test]
+    fn punctuated1_expects_an_item() {
+        let mut list = (punct(':'), punctuated1(ident(), punct(',')), punct(';'));
+        let ((_, items, _), _) = list.easy_parse(Input::parse_str(": a, b, ;").unwrap()).ok().unwrap();
+        assert_eq!(items.len(), 2);
+        let err = list.easy_parse(Input::parse_str(": ;").unwrap()).err().unwrap();
+        assert_eq!(err.position.index(), 1);
+        assert!(err.to_string().contains("Expected `at least one IDENT`"), "{}", err);
+    }
+
+    #[test]
+    fn permutation_expects_missing_clauses() {
+        let mut clauses = (permutation((keyword("where").with(ident()), keyword("limit").with(ident()))), eof());
+        let ((clauses, _), _) = clauses.easy_parse(Input::parse_str("limit a where b").unwrap()).ok().unwrap();
+        assert_eq!(clauses.0.unwrap().to_string(), "b");
+        assert_eq!(clauses.1.unwrap().to_string(), "a");
+        let mut clauses = (permutation((keyword("where").with(ident()), keyword("limit").with(ident()))), eof());
+        let err = clauses.easy_parse(Input::parse_str("where a 5").unwrap()).err().unwrap();
+        let message = err.to_string();
+        assert!(message.contains("`limit`") && !message.contains("`where`"), "{}", message);
+        assert!(message.contains("end of input"), "{}", message);
+    }
+
+    #[test]
+    fn signed_literals() {
+        let int = |source| signed_int_lit().parse(Input::parse_str(source).unwrap()).map(|(((value, _), _), _)| value);
+        let cases: &[(&str, Option<i128>)] = &[
+            ("5", Some(5)),
+            ("- 5", Some(-5)),
+            ("-0x10i32", Some(-16)),
+            ("170141183460469231731687303715884105727", Some(i128::MAX)),
+            ("170141183460469231731687303715884105728", None),
+            ("-170141183460469231731687303715884105728", Some(i128::MIN)),
+            ("-170141183460469231731687303715884105729", None),
+            ("-1u8", None),
+            ("1.5", None),
+        ];
+        for &(source, expected) in cases {
+            assert_eq!(int(source).ok(), expected, "{}", source);
+        }
+        let float = |source| signed_float_lit().parse(Input::parse_str(source).unwrap()).map(|((value, _), _)| value);
+        assert_eq!(float("-1.5").ok(), Some(-1.5));
+        assert!(float("-1").is_err());
+    }
+
+    #[test]
+    fn close_angle_leaves_rest_of_operator() {
+        let mut ty = (ident(), punct('<'), ident(), punct('<'), ident(), close_angle(), close_angle());
+        assert!(ty.parse(Input::parse_str("Vec<Vec<u8>>").unwrap()).is_ok());
+        let mut assign = (ident(), punct('<'), ident(), close_angle(), puncts(">="));
+        assert!(assign.parse(Input::parse_str("a<b>>=").unwrap()).is_ok());
+        let mut assign = (ident(), punct('<'), ident(), close_angle(), puncts("="));
+        assert!(assign.parse(Input::parse_str("a<b>=").unwrap()).is_ok());
+        assert!(close_angle().parse(Input::parse_str("<").unwrap()).is_err());
+    }
+
+    #[test]
+    fn nested_keeps_options() {
+        let (mut inner, rest) = nested().parse(Input::parse_str("{ a ( b ) } c").unwrap().shallow()).unwrap();
+        assert_eq!(rest.len(), 1);
+        assert_eq!(inner.len(), 2);
+        assert!(matches!(inner.nth(1), Some(Token::Group(_))));
+        assert_eq!(format!("{:?}", inner.position().into_span()), format!("{:?}", inner.eof_span()));
+
+        let outer = Input::parse_str("[ x ] y").unwrap().with_max_depth(1);
+        let (inner, _) = nested().easy_parse(outer).unwrap();
+        assert_eq!(inner.len(), 1);
+        assert!(nested().easy_parse(inner).is_err());
+    }
+
+    #[test]
+    fn literal_matching_compares_text() {
+        let matches = |text, source| literal_matching(text).parse(Input::parse_str(source).unwrap()).is_ok();
+        assert!(matches("\"GET\"", "\"GET\""));
+        assert!(matches("0u8", "0u8"));
+        assert!(!matches("\"GET\"", "\"GETS\""));
+        assert!(!matches("\"GET\"", "\"GE\""));
+        assert!(!matches("0u8", "0"));
+    }
+
+    #[test]
+    fn path_stops_before_non_ident_segment() {
+        let (parsed, rest) = path().parse(Input::parse_str("::foo::bar::Baz").unwrap()).unwrap();
+        assert!(parsed.leading_colon);
+        assert_eq!(parsed.segments.len(), 3);
+        assert!(rest.is_empty());
+        for &(source, segments, rest_len) in &[("foo::<T>", 1, 5), ("foo::*", 1, 3), ("foo::bar::{a}", 2, 5)] {
+            let (parsed, rest) = path().parse(Input::parse_str(source).unwrap()).unwrap();
+            assert_eq!((parsed.segments.len(), rest.len()), (segments, rest_len), "{}", source);
+        }
+    }
+
+    #[test]
+    fn spanned_at_end_of_nested_input() {
+        let (inner, _) = nested().easy_parse(Input::parse_str("( a b )").unwrap()).unwrap();
+        let tokens: Vec<Token> = inner.tokens().cloned().collect();
+        let ((_, span), _) = spanned((ident(), ident())).parse(inner).unwrap();
+        assert_eq!(format!("{:?}", span), format!("{:?}", join_spans(tokens[0].span(), tokens[1].span())));
     }
 }
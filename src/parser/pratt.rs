@@ -0,0 +1,287 @@
+//! An operator-precedence (Pratt) expression parser for `Token`s.
+//!
+//! ```rust
+//! # extern crate proc_macro;
+//! use combine::Parser;
+//! use combine_proc_macro::Input;
+//! use combine_proc_macro::parser::int_lit;
+//! use combine_proc_macro::parser::pratt::{Assoc, Pratt};
+//!
+//! let mut expr = Pratt::new(int_lit().map(|((value, _), _)| value as i64))
+//!     .infix("+", 1, Assoc::Left, |l, _, r| l + r)
+//!     .infix("-", 1, Assoc::Left, |l, _, r| l - r)
+//!     .infix("*", 2, Assoc::Left, |l, _, r| l * r)
+//!     .prefix("-", 3, |_, value| -value);
+//!
+//! let input = Input::from("1 + 2 * -3".parse::<proc_macro2::TokenStream>().unwrap());
+//! let value = expr.easy_parse(input.with_lookahead(2)).map(|(value, _)| value);
+//! assert_eq!(value, Ok(-5));
+//! ```
+
+use crate::input::{with_ident_str, Token};
+use super::parse_joint_puncts;
+use combine::{ParseError, Parser, Stream, StreamOnce};
+use combine::error::{ConsumedResult, FastResult::*, Info, Tracked};
+use proc_macro2::Span;
+
+/// The associativity of an infix operator.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Assoc {
+    /// `a - b - c` is parsed as `(a - b) - c`.
+    Left,
+    /// `a = b = c` is parsed as `a = (b = c)`.
+    Right,
+}
+
+type PrefixFn<T> = Box<dyn Fn(Span, T) -> T>;
+type InfixFn<T> = Box<dyn Fn(T, Span, T) -> T>;
+type PostfixFn<T> = Box<dyn Fn(T, Span) -> T>;
+
+/// A builder for an expression parser over `operand`s combined with prefix, infix, and postfix operators.
+///
+/// Operators are either keywords (e.g. `"and"`) or punctuation (e.g. `"+"` or `"<<="`), where
+/// multi-character punctuation must be joint (see `parser::puncts`). Operators with a higher
+/// precedence bind more tightly. When several operators match, the longest one is used.
+pub struct Pratt<P>
+where
+    P: Parser,
+{
+    operand: P,
+    prefix: Vec<(&'static str, u32, PrefixFn<P::Output>)>,
+    infix: Vec<(&'static str, u32, Assoc, InfixFn<P::Output>)>,
+    postfix: Vec<(&'static str, u32, PostfixFn<P::Output>)>,
+}
+
+impl<I, P> Pratt<P>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    P: Parser<Input = I>,
+{
+    /// Creates an expression parser with no operators, parsing only `operand`.
+    pub fn new(operand: P) -> Pratt<P> {
+        Pratt { operand, prefix: Vec::new(), infix: Vec::new(), postfix: Vec::new() }
+    }
+
+    /// Adds a prefix operator (e.g. `-x`), passing the operator's span and operand to `build`.
+    pub fn prefix<F>(mut self, op: &'static str, precedence: u32, build: F) -> Pratt<P>
+    where
+        F: Fn(Span, P::Output) -> P::Output + 'static,
+    {
+        self.prefix.push((op, precedence, Box::new(build)));
+        self.prefix.sort_by_key(|(op, _, _)| std::cmp::Reverse(op.len()));
+        self
+    }
+
+    /// Adds an infix operator (e.g. `x + y`), passing both operands and the operator's span to `build`.
+    pub fn infix<F>(mut self, op: &'static str, precedence: u32, assoc: Assoc, build: F) -> Pratt<P>
+    where
+        F: Fn(P::Output, Span, P::Output) -> P::Output + 'static,
+    {
+        self.infix.push((op, precedence, assoc, Box::new(build)));
+        self.infix.sort_by_key(|(op, _, _, _)| std::cmp::Reverse(op.len()));
+        self
+    }
+
+    /// Adds a postfix operator (e.g. `x?`), passing the operand and the operator's span to `build`.
+    pub fn postfix<F>(mut self, op: &'static str, precedence: u32, build: F) -> Pratt<P>
+    where
+        F: Fn(P::Output, Span) -> P::Output + 'static,
+    {
+        self.postfix.push((op, precedence, Box::new(build)));
+        self.postfix.sort_by_key(|(op, _, _)| std::cmp::Reverse(op.len()));
+        self
+    }
+
+    /// Parses an expression whose operators all bind at least as tightly as `min_power`.
+    fn parse_expr(&mut self, input: &mut I, min_power: u32, consumed: &mut bool) -> Result<P::Output, Tracked<I::Error>> {
+        let mut lhs = match self.match_prefix(input) {
+            Some((index, span)) => {
+                *consumed = true;
+                let power = self.prefix[index].1 * 2 + 1;
+                let operand = self.parse_expr(input, power, consumed)?;
+                (self.prefix[index].2)(span, operand)
+            }
+            None => self.parse_operand(input, consumed)?,
+        };
+        loop {
+            // Find the longest operator first, so e.g. `<` can't match the start of `<<`
+            let postfix = self.postfix.iter().position(|(op, _, _)| match_op(input, op, false).is_some());
+            let infix = self.infix.iter().position(|(op, _, _, _)| match_op(input, op, false).is_some());
+            let postfix_len = postfix.map_or(0, |index| self.postfix[index].0.len());
+            let infix_len = infix.map_or(0, |index| self.infix[index].0.len());
+            match (postfix, infix) {
+                (Some(index), _) if postfix_len >= infix_len => {
+                    if self.postfix[index].1 * 2 + 1 < min_power {
+                        return Ok(lhs);
+                    }
+                    let span = match_op(input, self.postfix[index].0, true).unwrap();
+                    *consumed = true;
+                    lhs = (self.postfix[index].2)(lhs, span);
+                }
+                (_, Some(index)) => {
+                    let (op, precedence, assoc, _) = &self.infix[index];
+                    let (op, precedence, assoc) = (*op, *precedence, *assoc);
+                    let (left, right) = match assoc {
+                        Assoc::Left => (precedence * 2 + 1, precedence * 2 + 2),
+                        Assoc::Right => (precedence * 2 + 2, precedence * 2 + 1),
+                    };
+                    if left < min_power {
+                        return Ok(lhs);
+                    }
+                    let span = match_op(input, op, true).unwrap();
+                    *consumed = true;
+                    let rhs = self.parse_expr(input, right, consumed)?;
+                    lhs = (self.infix[index].3)(lhs, span, rhs);
+                }
+                _ => return Ok(lhs),
+            }
+        }
+    }
+
+    fn match_prefix(&mut self, input: &mut I) -> Option<(usize, Span)> {
+        self.prefix.iter().enumerate().find_map(|(index, (op, _, _))| match_op(input, op, true).map(|span| (index, span)))
+    }
+
+    fn parse_operand(&mut self, input: &mut I, consumed: &mut bool) -> Result<P::Output, Tracked<I::Error>> {
+        match self.operand.parse_lazy(input) {
+            EmptyOk(value) => Ok(value),
+            ConsumedOk(value) => {
+                *consumed = true;
+                Ok(value)
+            }
+            EmptyErr(mut err) => {
+                if *consumed {
+                    self.operand.add_error(&mut err);
+                }
+                Err(err)
+            }
+            ConsumedErr(err) => {
+                *consumed = true;
+                Err(err.into())
+            }
+        }
+    }
+}
+
+impl<I, P> Parser for Pratt<P>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+    P: Parser<Input = I>,
+{
+    type Input = I;
+    type Output = P::Output;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let mut consumed = false;
+        match self.parse_expr(input, 0, &mut consumed) {
+            Ok(value) if consumed => ConsumedOk(value),
+            Ok(value) => EmptyOk(value),
+            Err(err) if consumed => ConsumedErr(err.error),
+            Err(err) => EmptyErr(err),
+        }
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        self.operand.add_error(errors);
+        for (op, _, _) in &self.prefix {
+            errors.error.add_expected(Info::Borrowed(op));
+        }
+    }
+}
+
+/// Matches the operator `op` at the start of the input, returning the span of its first token.
+///
+/// The input is only advanced past the operator if it matched and `consume` is true.
+fn match_op<I>(input: &mut I, op: &'static str, consume: bool) -> Option<Span>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    let checkpoint = input.checkpoint();
+    let span = if op.starts_with(|ch: char| ch.is_alphabetic() || ch == '_') {
+        match input.uncons() {
+            Ok(Token::Ident(ref ident)) if with_ident_str(ident, |word| word == op) => Some(ident.span()),
+            _ => None,
+        }
+    } else {
+        match parse_joint_puncts(input, op.chars()) {
+            ConsumedOk(puncts) | EmptyOk(puncts) => puncts.first().map(|punct| punct.span()),
+            EmptyErr(_) | ConsumedErr(_) => None,
+        }
+    };
+    if span.is_none() || !consume {
+        input.reset(checkpoint);
+    }
+    span
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Assoc, Pratt};
+    use crate::Input;
+    use crate::parser::{ident, int_lit};
+    use combine::Parser;
+
+    fn calc() -> Pratt<impl Parser<Input = combine::easy::Stream<Input>, Output = i64>> {
+        Pratt::new(int_lit().map(|((value, _), _)| value as i64))
+            .infix("+", 3, Assoc::Left, |l, _, r| l + r)
+            .infix("-", 3, Assoc::Left, |l, _, r| l - r)
+            .infix("*", 4, Assoc::Left, |l, _, r| l * r)
+            .infix("^", 5, Assoc::Right, |l, _, r| l.pow(r as u32))
+            .infix("<<", 1, Assoc::Left, |l, _, r| l << r)
+            .infix("<", 5, Assoc::Left, |l, _, r| (l < r) as i64)
+            .prefix("-", 6, |_, value| -value)
+            .postfix("!", 7, |value, _| (1..=value).product())
+    }
+
+    fn eval(source: &str) -> Result<i64, String> {
+        calc().skip(crate::parser::eof())
+            .easy_parse(Input::parse_str(source).unwrap())
+            .map(|(value, _)| value)
+            .map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(eval("1 + 2 * 3"), Ok(7));
+        assert_eq!(eval("2 * 3 + 1"), Ok(7));
+        assert_eq!(eval("1 + 2 * 3 ^ 2"), Ok(19));
+    }
+
+    #[test]
+    fn associativity() {
+        assert_eq!(eval("10 - 3 - 2"), Ok(5));
+        assert_eq!(eval("2 ^ 3 ^ 2"), Ok(512));
+    }
+
+    #[test]
+    fn prefix_and_postfix() {
+        assert_eq!(eval("- 2 * 3"), Ok(-6));
+        assert_eq!(eval("3 ! + 1"), Ok(7));
+        assert_eq!(eval("- 3 !"), Ok(-6));
+        assert_eq!(eval("1 - - 2"), Ok(3));
+    }
+
+    #[test]
+    fn overlapping_operators() {
+        assert_eq!(eval("1 + 2 << 3"), Ok(24));
+        assert_eq!(eval("1 << 2 + 1"), Ok(8));
+        assert_eq!(eval("1 < 2 + 1"), Ok(2));
+        assert!(eval("1 < < 2").is_err());
+    }
+
+    #[test]
+    fn keyword_operators() {
+        let mut expr = Pratt::new(ident().map(|ident| ident == "true"))
+            .infix("or", 1, Assoc::Left, |l, _, r| l || r)
+            .infix("and", 2, Assoc::Left, |l, _, r| l && r)
+            .prefix("not", 3, |_, value| !value);
+        let mut eval = |source| expr.easy_parse(Input::parse_str(source).unwrap()).map(|(value, _)| value);
+        assert_eq!(eval("true or false and false"), Ok(true));
+        assert_eq!(eval("not true or true"), Ok(true));
+        assert_eq!(eval("not false and false"), Ok(false));
+    }
+}
@@ -0,0 +1,139 @@
+//! Parsers for common Rust punctuation, each returning the span of the punctuation.
+//!
+//! Single-char punctuation is not matched when it's the start of a longer operator that
+//! shares its first char (e.g. `colon` doesn't match the first `:` of `::`).
+
+use crate::input::Token;
+use super::op_seq;
+use combine::{ParseError, Parser, Stream, StreamOnce};
+use combine::error::{ConsumedResult, FastResult::*, Info, Tracked};
+use proc_macro2::{Spacing, Span};
+use std::marker::PhantomData;
+
+/// Parses `,`.
+pub fn comma<I>() -> Single<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    Single(',', &[], PhantomData)
+}
+
+/// Parses `;`.
+pub fn semi<I>() -> Single<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    Single(';', &[], PhantomData)
+}
+
+/// Parses `#`.
+pub fn pound<I>() -> Single<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    Single('#', &[], PhantomData)
+}
+
+/// Parses `:` (but not `::`).
+pub fn colon<I>() -> Single<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    Single(':', &[':'], PhantomData)
+}
+
+/// Parses `=` (but not `==` or `=>`).
+pub fn eq<I>() -> Single<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    Single('=', &['=', '>'], PhantomData)
+}
+
+/// Parses `.` (but not `..`).
+pub fn dot<I>() -> Single<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    Single('.', &['.'], PhantomData)
+}
+
+/// Parses `::`.
+pub fn path_sep<I>() -> impl Parser<Input = I, Output = Span>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    op_seq("::")
+}
+
+/// Parses `->`.
+pub fn arrow<I>() -> impl Parser<Input = I, Output = Span>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    op_seq("->")
+}
+
+/// Parses `=>`.
+pub fn fat_arrow<I>() -> impl Parser<Input = I, Output = Span>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    op_seq("=>")
+}
+
+#[derive(Copy, Clone)]
+/// Represents the return type of the single-char punctuation parsers (e.g. `comma`).
+///
+/// Holds the punct's char and the chars that would make it part of a longer operator when
+/// the punct is joint to them.
+pub struct Single<I>(char, &'static [char], PhantomData<fn(I) -> I>)
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>;
+
+impl<I> Parser for Single<I>
+where
+    I: Stream<Item = Token>,
+    I::Error: ParseError<I::Item, I::Range, I::Position>,
+{
+    type Input = I;
+    type Output = Span;
+    type PartialState = ();
+
+    fn parse_lazy(&mut self, input: &mut Self::Input) -> ConsumedResult<Self::Output, Self::Input> {
+        let position = input.position();
+        let checkpoint = input.checkpoint();
+        if let Ok(Token::Punct(punct)) = input.uncons() {
+            if punct.as_char() == self.0 {
+                if punct.spacing() == Spacing::Alone {
+                    return ConsumedOk(punct.span());
+                }
+                let after = input.checkpoint();
+                let longer = match input.uncons() {
+                    Ok(Token::Punct(next)) => self.1.contains(&next.as_char()),
+                    _ => false,
+                };
+                if !longer {
+                    input.reset(after);
+                    return ConsumedOk(punct.span());
+                }
+            }
+        }
+        input.reset(checkpoint);
+        EmptyErr(I::Error::empty(position).into())
+    }
+
+    fn add_error(&mut self, errors: &mut Tracked<<Self::Input as StreamOnce>::Error>) {
+        errors.error.add_expected(Info::Token(Token::Punct(proc_macro2::Punct::new(self.0, Spacing::Alone))));
+    }
+}
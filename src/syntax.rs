@@ -0,0 +1,118 @@
+//! Values for common pieces of Rust syntax recognized by the parsers in `parser`.
+
+use crate::input::join_spans;
+use proc_macro2::{Ident, Literal, Span, TokenStream};
+use std::fmt;
+
+/// A lifetime such as `'a`, parsed by `parser::lifetime`.
+#[derive(Clone, Debug)]
+pub struct Lifetime {
+    /// The span of the leading `'`.
+    pub apostrophe: Span,
+    pub ident: Ident,
+}
+
+impl Lifetime {
+    /// Returns the span covering the whole lifetime (see `Span::join`).
+    pub fn span(&self) -> Span {
+        join_spans(self.apostrophe, self.ident.span())
+    }
+}
+
+impl fmt::Display for Lifetime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "'{}", self.ident)
+    }
+}
+
+/// A path of idents separated by `::` such as `std::fmt::Display`, parsed by `parser::path`.
+#[derive(Clone, Debug)]
+pub struct Path {
+    /// Whether the path started with a `::` (e.g. `::std::fmt`).
+    pub leading_colon: bool,
+    pub segments: Vec<Ident>,
+}
+
+impl Path {
+    /// Returns the span covering the whole path (see `Span::join`).
+    pub fn span(&self) -> Span {
+        let first = self.segments.first().map_or_else(Span::call_site, Ident::span);
+        let last = self.segments.last().map_or_else(Span::call_site, Ident::span);
+        join_spans(first, last)
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.leading_colon {
+            write!(f, "::")?;
+        }
+        for (i, segment) in self.segments.iter().enumerate() {
+            if i > 0 {
+                write!(f, "::")?;
+            }
+            write!(f, "{}", segment)?;
+        }
+        Ok(())
+    }
+}
+
+/// An outer attribute such as `#[doc = "..."]`, parsed by `parser::attribute`.
+#[derive(Clone, Debug)]
+pub struct Attribute {
+    /// The span of the leading `#`.
+    pub pound: Span,
+    pub path: Path,
+    /// The tokens following the path inside the brackets (e.g. `= "..."` or `(a, b)`).
+    pub tokens: TokenStream,
+}
+
+/// An attribute argument such as `flag`, `key = "value"`, or `feature(any, all)`, parsed by
+/// `parser::meta`.
+#[derive(Clone, Debug)]
+pub enum Meta {
+    /// A bare path, e.g. `flag` or `serde::skip`.
+    Path(Path),
+    /// A path with a parenthesized list of arguments, e.g. `feature(any, all)`.
+    List(Path, Vec<Meta>),
+    /// A path with a literal value, e.g. `rename = "id"`.
+    NameValue(Path, Literal),
+    /// A bare literal inside a list, e.g. the `"x"` in `values("x")`.
+    Literal(Literal),
+}
+
+impl Meta {
+    /// Returns the path of the argument, or `None` for a bare literal.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Meta::Path(path) | Meta::List(path, _) | Meta::NameValue(path, _) => Some(path),
+            Meta::Literal(_) => None,
+        }
+    }
+}
+
+/// The visibility of an item such as `pub` or `pub(crate)`, parsed by `parser::visibility`.
+#[derive(Clone, Debug)]
+pub enum Visibility {
+    /// Public visibility, i.e. `pub`.
+    Public(Ident),
+    /// Visibility restricted to a module, i.e. `pub(crate)`, `pub(self)`, `pub(super)`, or `pub(in path)`.
+    Restricted(Ident, Path),
+    /// No visibility was specified.
+    Inherited,
+}
+
+impl fmt::Display for Visibility {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Visibility::Public(_) => write!(f, "pub"),
+            Visibility::Restricted(_, path) => match path.segments.as_slice() {
+                [word] if !path.leading_colon && ["crate", "self", "super"].iter().any(|kw| word == kw) => {
+                    write!(f, "pub({})", word)
+                }
+                _ => write!(f, "pub(in {})", path),
+            },
+            Visibility::Inherited => Ok(()),
+        }
+    }
+}